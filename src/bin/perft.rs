@@ -0,0 +1,55 @@
+// Counts the legal move tree from a position, for checking the move
+// generator against published perft numbers. Needs no graphics, so it also
+// builds with --no-default-features:
+//
+//     cargo run --release --no-default-features --bin perft -- <depth> [fen]
+//
+// With the gui feature the same count is `chess-engine perft <depth> [fen]`.
+
+extern crate chess_engine;
+
+use std::time::Instant;
+use chess_engine::fen::{Fen, START_POSITION};
+
+// Prints the node count below each legal move, then the total. The FEN may
+// be passed as one quoted argument or as its separate fields.
+fn main() {
+    let args: Vec<String> = std::env::args().skip(1).collect();
+
+    let depth = match args.first().and_then(|depth| depth.parse::<u32>().ok()) {
+        Some(depth) => depth,
+        None => {
+            println!("Usage: perft <depth> [fen]");
+            return;
+        }
+    };
+
+    let fen_manager = Fen {
+        fen_string: if args.len() < 2 { START_POSITION.to_string() } else { args[1..].join(" ") }
+    };
+
+    let mut position = match fen_manager.parse() {
+        Ok(position) => position,
+        Err(error) => {
+            println!("Invalid FEN: {}", error);
+            return;
+        }
+    };
+
+    let started = Instant::now();
+    let mut total: u64 = 0;
+
+    // No moves to list at depth 0, the position itself is the one node
+    if depth == 0 {
+        total = position.perft(depth);
+    }
+
+    for (chess_move, nodes) in position.divide(depth) {
+        println!("{}: {}", chess_move.to_uci(), nodes);
+        total += nodes;
+    }
+
+    println!();
+    println!("Nodes searched: {}", total);
+    println!("Time: {} ms", started.elapsed().as_millis());
+}
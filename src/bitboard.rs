@@ -0,0 +1,272 @@
+// Bitboards use the same square numbering as the rest of the crate: bit 0 is
+// the top left square (a8) and bit 63 the bottom right (h1).
+//
+// Knight, king and pawn attacks are plain lookup tables. Rook and bishop
+// attacks use magic bitboards: the blockers on a square's relevant rays are
+// multiplied by a magic number and shifted down to index a table of
+// precomputed attack sets. The tables are filled once, on first use.
+
+use std::sync::OnceLock;
+use square::Square;
+
+pub const PAWN: usize = 0;
+pub const KNIGHT: usize = 1;
+pub const BISHOP: usize = 2;
+pub const ROOK: usize = 3;
+pub const QUEEN: usize = 4;
+pub const KING: usize = 5;
+
+pub const PIECE_NAMES: [&str; 6] = ["Pawn", "Knight", "Bishop", "Rook", "Queen", "King"];
+
+pub const PIECE_LETTERS: [char; 6] = ['P', 'N', 'B', 'R', 'Q', 'K'];
+
+pub const ROW_0: u64 = 0xff;
+pub const ROW_7: u64 = 0xff << 56;
+
+// Steps are (file, rank) offsets, as taken by Square::offset
+const KNIGHT_JUMPS: [(i32, i32); 8] = [
+    (1, 2), (2, 1), (2, -1), (1, -2),
+    (-1, -2), (-2, -1), (-2, 1), (-1, 2),
+];
+
+const ORTHOGONAL_STEPS: [(i32, i32); 4] = [(1, 0), (-1, 0), (0, 1), (0, -1)];
+const DIAGONAL_STEPS: [(i32, i32); 4] = [(1, 1), (1, -1), (-1, 1), (-1, -1)];
+
+struct Magic {
+    mask: u64,
+    magic: u64,
+    shift: u32,
+    offset: usize,
+}
+
+struct AttackTables {
+    knight: [u64; 64],
+    king: [u64; 64],
+    // Indexed [colour][square] with white = 0
+    pawn: [[u64; 64]; 2],
+    rook_magics: Vec<Magic>,
+    bishop_magics: Vec<Magic>,
+    rook_attacks: Vec<u64>,
+    bishop_attacks: Vec<u64>,
+}
+
+static TABLES: OnceLock<AttackTables> = OnceLock::new();
+
+fn tables() -> &'static AttackTables {
+    TABLES.get_or_init(AttackTables::new)
+}
+
+pub fn kind_from_name(name: &str) -> Option<usize> {
+    PIECE_NAMES.iter().position(|&piece_name| piece_name == name)
+}
+
+pub fn square_bit(square: u32) -> u64 {
+    1u64 << square
+}
+
+// Removes the lowest set bit and returns its square, None once the board is empty
+pub fn pop_square(bitboard: &mut u64) -> Option<u32> {
+    if *bitboard == 0 {
+        return None;
+    }
+
+    let square = bitboard.trailing_zeros();
+    *bitboard &= *bitboard - 1;
+
+    Some(square)
+}
+
+fn jump_attacks(square: Square, jumps: &[(i32, i32)]) -> u64 {
+    jumps.iter()
+        .filter_map(|&(file_offset, rank_offset)| square.offset(file_offset, rank_offset))
+        .fold(0, |attacks, target| attacks | square_bit(target.index()))
+}
+
+// Slow ray walk used to fill the magic tables. Every ray runs up to and
+// including the first blocker.
+fn ray_attacks(square: Square, occupancy: u64, steps: &[(i32, i32)]) -> u64 {
+    let mut attacks = 0;
+
+    for &(file_step, rank_step) in steps.iter() {
+        let mut current = square;
+
+        while let Some(next) = current.offset(file_step, rank_step) {
+            attacks |= square_bit(next.index());
+
+            if occupancy & square_bit(next.index()) != 0 {
+                break;
+            }
+
+            current = next;
+        }
+    }
+
+    attacks
+}
+
+// The squares whose occupancy changes the attack set. The last square of each
+// ray is left out since it is attacked whether or not something stands there.
+fn relevant_mask(square: Square, steps: &[(i32, i32)]) -> u64 {
+    let mut mask = 0;
+
+    for &(file_step, rank_step) in steps.iter() {
+        let mut current = square;
+
+        while let Some(next) = current.offset(file_step, rank_step) {
+            if next.offset(file_step, rank_step).is_none() {
+                break;
+            }
+
+            mask |= square_bit(next.index());
+            current = next;
+        }
+    }
+
+    mask
+}
+
+// Indexed by square. Found with a random search over sparse candidates for
+// this square numbering; they will not work for the usual a1 = 0 layout.
+const ROOK_MAGICS: [u64; 64] = [
+    0x0480046281400010, 0x80c0200010004000, 0x8780200008300180, 0x8880060800100080,
+    0x2100030010080084, 0x0100040001000802, 0x0200040800810200, 0x0580008002407100,
+    0x1000800080400020, 0x0080401000402001, 0x800c802002100880, 0x800a002200884010,
+    0x2046002008108600, 0x0222009002000804, 0x100b000421001200, 0x0240800100004080,
+    0x4540008020408006, 0x8010054020084002, 0x7d10010100200040, 0x1408008010000882,
+    0x4408010005000810, 0x001e008004000280, 0x0230040001080210, 0x0000020004004081,
+    0x0100400080208001, 0x1000842300400100, 0x1060100080200082, 0x3219004b00100020,
+    0x9010080080800400, 0x8440020080800400, 0x6008010080800200, 0x4123008200010044,
+    0x0280002001400240, 0x0220100040400020, 0x0060801003802008, 0x0008100080800800,
+    0x0105000801001004, 0x100b000803000400, 0x0000024814001021, 0x00408000c2802100,
+    0x4c40004020808002, 0x4410500420024000, 0x00c0100020008080, 0x0000100008008080,
+    0x8002000804220011, 0x0802000804010100, 0x0243100201040008, 0x0000009100420014,
+    0x1000400280022480, 0x0020200040100040, 0x00a000100800c140, 0x0410001408008080,
+    0x0000080004008080, 0x0100020004008080, 0x0303000200040300, 0x1480006104008200,
+    0x00008002204a1101, 0x1040090010224081, 0x4300c0200011000d, 0x8002041001002009,
+    0x2005000800020411, 0x110a008408100102, 0x0006000108008402, 0x0200002900884402,
+];
+
+const BISHOP_MAGICS: [u64; 64] = [
+    0x48081010008a2a80, 0x000948110c0b2081, 0x0944140400500000, 0x4984104a00000101,
+    0x4004030818283008, 0x0206012462000121, 0x1a02013008040001, 0x0001008044200440,
+    0x0000312208080880, 0x0220021002009900, 0x8080880801082000, 0x000c11040080102a,
+    0x1402440421000210, 0x0010120802080a81, 0x0080084202104028, 0x1100002082082082,
+    0x0008403429080820, 0x8104868204040412, 0x6424084043060030, 0x1108000420401000,
+    0x9004101202020240, 0x0032400608200412, 0x0001009610822080, 0x0008403429080820,
+    0x0008068340104200, 0x0010102858090121, 0x81004c0018080313, 0x4048080004820002,
+    0x000900401c004049, 0x0009420121c1101c, 0x4828504005040211, 0x4828504005040211,
+    0x0041041381202000, 0x01008c1005601680, 0x01d010900002040a, 0x4040020080080080,
+    0x4801080200802200, 0x4801080200802200, 0x0010046108108080, 0x90409090810a0220,
+    0x8004020242201020, 0x8004020242201020, 0x0202010028020480, 0x0000041144000801,
+    0x00002000a4021080, 0x0504090045040200, 0x8182041102094400, 0x0550008100480101,
+    0xc002080404040400, 0x0382004108292000, 0x12000100a8040020, 0xa005020442088020,
+    0x2000001102020300, 0x000021e0420c8808, 0x3060200484888400, 0x01280101021a0802,
+    0x1030820110010500, 0x0080012608025800, 0x0002810084008800, 0x800080000c208800,
+    0xa408002140028204, 0x0010006020322084, 0x0210401044110050, 0x40106000a1160020,
+];
+
+// Builds the lookup for every square from its magic and fills one shared
+// attack table for the piece
+fn build_magics(steps: &[(i32, i32)], magic_numbers: &[u64; 64]) -> (Vec<Magic>, Vec<u64>) {
+    let mut magics: Vec<Magic> = Vec::with_capacity(64);
+    let mut table: Vec<u64> = vec![];
+
+    for square in (0..64).filter_map(Square::from_index) {
+        let mask = relevant_mask(square, steps);
+        let bits = mask.count_ones();
+        let magic = Magic { mask, magic: magic_numbers[square.index() as usize], shift: 64 - bits, offset: table.len() };
+
+        table.extend(std::iter::repeat_n(0, 1usize << bits));
+
+        // Every subset of the mask, walked with the carry-rippler trick
+        let mut subset: u64 = 0;
+
+        loop {
+            let index = magic_index(&magic, subset);
+            let attacks = ray_attacks(square, subset, steps);
+
+            assert!(table[index] == 0 || table[index] == attacks, "bad magic for square {}", square);
+            table[index] = attacks;
+
+            subset = subset.wrapping_sub(mask) & mask;
+
+            if subset == 0 {
+                break;
+            }
+        }
+
+        magics.push(magic);
+    }
+
+    (magics, table)
+}
+
+impl AttackTables {
+    fn new() -> AttackTables {
+        let mut knight = [0; 64];
+        let mut king = [0; 64];
+        let mut pawn = [[0; 64]; 2];
+
+        for index in 0..64 {
+            let square = Square::from_index(index as u32).unwrap();
+
+            knight[index] = jump_attacks(square, &KNIGHT_JUMPS);
+
+            let mut king_steps = ORTHOGONAL_STEPS.to_vec();
+            king_steps.extend_from_slice(&DIAGONAL_STEPS);
+            king[index] = jump_attacks(square, &king_steps);
+
+            // White pawns capture towards rank 8, black pawns towards rank 1
+            pawn[0][index] = jump_attacks(square, &[(-1, 1), (1, 1)]);
+            pawn[1][index] = jump_attacks(square, &[(-1, -1), (1, -1)]);
+        }
+
+        let (rook_magics, rook_attacks) = build_magics(&ORTHOGONAL_STEPS, &ROOK_MAGICS);
+        let (bishop_magics, bishop_attacks) = build_magics(&DIAGONAL_STEPS, &BISHOP_MAGICS);
+
+        AttackTables {
+            knight,
+            king,
+            pawn,
+            rook_magics,
+            bishop_magics,
+            rook_attacks,
+            bishop_attacks,
+        }
+    }
+}
+
+fn magic_index(magic: &Magic, occupancy: u64) -> usize {
+    magic.offset + ((occupancy & magic.mask).wrapping_mul(magic.magic) >> magic.shift) as usize
+}
+
+pub fn knight_attacks(square: u32) -> u64 {
+    tables().knight[square as usize]
+}
+
+pub fn king_attacks(square: u32) -> u64 {
+    tables().king[square as usize]
+}
+
+// Squares a pawn of the given colour on `square` captures on
+pub fn pawn_attacks(square: u32, white: bool) -> u64 {
+    tables().pawn[if white { 0 } else { 1 }][square as usize]
+}
+
+pub fn rook_attacks(square: u32, occupancy: u64) -> u64 {
+    let tables = tables();
+    let magic = &tables.rook_magics[square as usize];
+
+    tables.rook_attacks[magic_index(magic, occupancy)]
+}
+
+pub fn bishop_attacks(square: u32, occupancy: u64) -> u64 {
+    let tables = tables();
+    let magic = &tables.bishop_magics[square as usize];
+
+    tables.bishop_attacks[magic_index(magic, occupancy)]
+}
+
+pub fn queen_attacks(square: u32, occupancy: u64) -> u64 {
+    rook_attacks(square, occupancy) | bishop_attacks(square, occupancy)
+}
@@ -0,0 +1,302 @@
+// Extended Position Description: the first four FEN fields followed by
+// operations, each an opcode and its operands ended by a semicolon, e.g.
+//
+//     r1bqkb1r/pppp1ppp/2n2n2/4p2Q/2B1P3/8/PPPP1PPP/RNB1K1NR w KQkq - bm Qxf7#; id "mate.001";
+//
+// The position fields go through Fen::parse. Move operands (bm, am, pv) are
+// kept as the SAN text they were written with.
+
+use std::error::Error;
+use std::fmt;
+use fen::{Fen, FenError};
+use position::Position;
+
+pub struct Epd {
+    pub epd_string: String
+}
+
+#[derive(Clone)]
+#[derive(PartialEq)]
+#[derive(Debug)]
+pub struct Operation {
+    pub opcode: String,
+    pub operands: Vec<String>,
+}
+
+#[derive(Clone)]
+#[derive(Debug)]
+pub struct EpdRecord {
+    pub position: Position,
+    // In the order they were read, and written back in that order
+    pub operations: Vec<Operation>,
+}
+
+// Columns count characters of the whole EPD string from 1, like FenError's
+#[derive(Clone)]
+#[derive(PartialEq)]
+#[derive(Debug)]
+pub enum EpdError {
+    Fen(FenError),
+    BadOpcode { column: usize },
+    UnterminatedString { column: usize },
+    MissingSemicolon { column: usize },
+    BadOperand { opcode: String, column: usize },
+}
+
+impl fmt::Display for EpdError {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        match *self {
+            EpdError::Fen(ref error) => write!(f, "{}", error),
+            EpdError::BadOpcode { column } =>
+                write!(f, "expected an opcode (column {})", column),
+            EpdError::UnterminatedString { column } =>
+                write!(f, "string is never closed (column {})", column),
+            EpdError::MissingSemicolon { column } =>
+                write!(f, "operation is not ended by ';' (column {})", column),
+            EpdError::BadOperand { ref opcode, column } =>
+                write!(f, "bad operand for '{}' (column {})", opcode, column),
+        }
+    }
+}
+
+impl Error for EpdError {}
+
+impl From<FenError> for EpdError {
+    fn from(error: FenError) -> EpdError {
+        EpdError::Fen(error)
+    }
+}
+
+impl Operation {
+    pub fn new(opcode: &str, operands: Vec<String>) -> Operation {
+        Operation {
+            opcode: opcode.to_string(),
+            operands,
+        }
+    }
+
+    // id and the c0-c9 comments always hold strings, anything else is only
+    // quoted when it couldn't be read back as a single operand
+    fn needs_quotes(&self, operand: &str) -> bool {
+        let is_comment = self.opcode.len() == 2
+            && self.opcode.starts_with('c')
+            && self.opcode.chars().nth(1).is_some_and(|c| c.is_ascii_digit());
+
+        self.opcode == "id" || is_comment || operand.is_empty()
+            || operand.contains(|c: char| c.is_whitespace() || c == ';')
+    }
+}
+
+impl Epd {
+    pub fn parse(&self) -> Result<EpdRecord, EpdError> {
+        let position_end = self.get_position_end();
+        let fen_manager = Fen { fen_string: self.epd_string[..position_end].to_string() };
+
+        let position = fen_manager.parse()?;
+        let start_column = self.epd_string[..position_end].chars().count() + 1;
+        let operations = self.parse_operations(&self.epd_string[position_end..], start_column)?;
+
+        Ok(EpdRecord { position, operations })
+    }
+
+    // Byte offset just past the fourth whitespace separated field
+    fn get_position_end(&self) -> usize {
+        let mut fields = 0;
+        let mut in_field = false;
+
+        for (offset, c) in self.epd_string.char_indices() {
+            if c.is_whitespace() {
+                if in_field {
+                    fields += 1;
+
+                    if fields == 4 {
+                        return offset;
+                    }
+                }
+
+                in_field = false;
+            } else {
+                in_field = true;
+            }
+        }
+
+        self.epd_string.len()
+    }
+
+    fn parse_operations(&self, text: &str, start_column: usize) -> Result<Vec<Operation>, EpdError> {
+        let mut operations: Vec<Operation> = vec![];
+        // Tokens of the operation being read, each with the column it starts at
+        let mut tokens: Vec<(usize, String)> = vec![];
+        let mut chars = text.chars().enumerate().peekable();
+
+        while let Some((offset, c)) = chars.next() {
+            let column = start_column + offset;
+
+            if c.is_whitespace() {
+                continue;
+            }
+
+            if c == ';' {
+                operations.push(self.build_operation(&tokens, column)?);
+                tokens.clear();
+                continue;
+            }
+
+            let mut token = String::new();
+
+            if c == '"' {
+                loop {
+                    match chars.next() {
+                        Some((_, '"')) => break,
+                        Some((_, inner)) => token.push(inner),
+                        None => return Err(EpdError::UnterminatedString { column }),
+                    }
+                }
+
+                // Quoted strings are always operands, never opcodes
+                if tokens.is_empty() {
+                    return Err(EpdError::BadOpcode { column });
+                }
+            } else {
+                token.push(c);
+
+                while let Some(&(_, next)) = chars.peek() {
+                    if next.is_whitespace() || next == ';' || next == '"' {
+                        break;
+                    }
+
+                    token.push(next);
+                    chars.next();
+                }
+            }
+
+            tokens.push((column, token));
+        }
+
+        if let Some(&(column, _)) = tokens.first() {
+            return Err(EpdError::MissingSemicolon { column });
+        }
+
+        Ok(operations)
+    }
+
+    // Opcodes start with a letter and hold only letters, digits and underscores
+    fn build_operation(&self, tokens: &[(usize, String)], semicolon_column: usize) -> Result<Operation, EpdError> {
+        let (column, opcode) = match tokens.first() {
+            Some(&(column, ref opcode)) => (column, opcode.clone()),
+            None => return Err(EpdError::BadOpcode { column: semicolon_column }),
+        };
+
+        let valid_opcode = opcode.chars().next().is_some_and(|c| c.is_ascii_alphabetic())
+            && opcode.chars().all(|c| c.is_ascii_alphanumeric() || c == '_');
+
+        if !valid_opcode {
+            return Err(EpdError::BadOpcode { column });
+        }
+
+        let operands: Vec<String> = tokens[1..].iter().map(|(_, operand)| operand.clone()).collect();
+
+        // The numeric opcodes take exactly one number
+        let numeric = match opcode.as_str() {
+            "acd" => operands.len() == 1 && operands[0].parse::<u32>().is_ok(),
+            "ce" => operands.len() == 1 && operands[0].parse::<i32>().is_ok(),
+            _ => true,
+        };
+
+        if !numeric {
+            let operand_column = tokens.get(1).map(|&(column, _)| column).unwrap_or(semicolon_column);
+
+            return Err(EpdError::BadOperand { opcode, column: operand_column });
+        }
+
+        Ok(Operation { opcode, operands })
+    }
+}
+
+impl EpdRecord {
+    pub fn new(position: Position) -> EpdRecord {
+        EpdRecord {
+            position,
+            operations: vec![],
+        }
+    }
+
+    pub fn get_operation(&self, opcode: &str) -> Option<&Operation> {
+        self.operations.iter().find(|operation| operation.opcode == opcode)
+    }
+
+    // Replaces the operation with the same opcode, or adds it at the end
+    pub fn set_operation(&mut self, operation: Operation) {
+        match self.operations.iter_mut().find(|existing| existing.opcode == operation.opcode) {
+            Some(existing) => *existing = operation,
+            None => self.operations.push(operation),
+        }
+    }
+
+    fn get_operands(&self, opcode: &str) -> Vec<&str> {
+        self.get_operation(opcode)
+            .map(|operation| operation.operands.iter().map(|operand| operand.as_str()).collect())
+            .unwrap_or_default()
+    }
+
+    fn get_single_operand(&self, opcode: &str) -> Option<&str> {
+        self.get_operation(opcode)
+            .and_then(|operation| operation.operands.first())
+            .map(|operand| operand.as_str())
+    }
+
+    pub fn get_best_moves(&self) -> Vec<&str> {
+        self.get_operands("bm")
+    }
+
+    pub fn get_avoid_moves(&self) -> Vec<&str> {
+        self.get_operands("am")
+    }
+
+    pub fn get_principal_variation(&self) -> Vec<&str> {
+        self.get_operands("pv")
+    }
+
+    pub fn get_id(&self) -> Option<&str> {
+        self.get_single_operand("id")
+    }
+
+    // The c0 to c9 comments
+    pub fn get_comment(&self, number: u32) -> Option<&str> {
+        self.get_single_operand(&format!("c{}", number))
+    }
+
+    // Analysis count depth, in plies
+    pub fn get_depth(&self) -> Option<u32> {
+        self.get_single_operand("acd").and_then(|depth| depth.parse().ok())
+    }
+
+    // Centipawn evaluation, from the side to move's point of view
+    pub fn get_evaluation(&self) -> Option<i32> {
+        self.get_single_operand("ce").and_then(|evaluation| evaluation.parse().ok())
+    }
+
+    // The first four FEN fields and every operation, in the form Epd::parse reads back
+    pub fn to_epd(&self) -> String {
+        let fen = self.position.to_fen();
+        let mut epd: Vec<String> = fen.split(' ').take(4).map(|field| field.to_string()).collect();
+
+        for operation in self.operations.iter() {
+            let mut written = operation.opcode.clone();
+
+            for operand in operation.operands.iter() {
+                if operation.needs_quotes(operand) {
+                    written.push_str(&format!(" \"{}\"", operand));
+                } else {
+                    written.push(' ');
+                    written.push_str(operand);
+                }
+            }
+
+            written.push(';');
+            epd.push(written);
+        }
+
+        epd.join(" ")
+    }
+}
@@ -1,102 +1,479 @@
 use std::collections::HashMap;
-use ::{Piece, Tile};
-use main;
+use std::error::Error;
+use std::fmt;
+use std::fs;
+use std::io;
+use std::path::Path;
+use bitboard::{square_bit, KING, PAWN, ROOK};
+use position::{CastlingRights, Piece, Position};
+use square::Square;
+
+// Standard FEN: uppercase letters are white, lowercase black, and the
+// placement starts from rank 8, which is board_index 0.
+pub const START_POSITION: &str = "rnbqkbnr/pppppppp/8/8/8/8/PPPPPPPP/RNBQKBNR w KQkq - 0 1";
 
 pub struct Fen {
     pub fen_string: String
 }
 
+// Everything Fen::parse rejects. Columns count characters of the whole FEN
+// string from 1 and point at the offending character, or at the start of the
+// field when the problem isn't a single character.
+#[derive(Clone)]
+#[derive(PartialEq)]
+#[derive(Debug)]
+pub enum FenError {
+    // Fields are numbered from 1: placement, side, castling, en passant, halfmove, fullmove
+    MissingField { field: usize, column: usize },
+    TooManyFields { column: usize },
+    BadRankCount { ranks: usize, column: usize },
+    // Ranks are numbered as on the board, 8 being the first in the string
+    BadRankLength { rank: u32, squares: u32, column: usize },
+    UnknownPieceLetter { letter: char, column: usize },
+    MissingKing { white: bool, column: usize },
+    TooManyKings { white: bool, column: usize },
+    PawnOnBackRank { column: usize },
+    BadSideToMove { column: usize },
+    BadCastlingLetter { letter: char, column: usize },
+    ImpossibleCastlingRights { letter: char, column: usize },
+    BadEnPassantSquare { column: usize },
+    BadMoveCounter { field: usize, column: usize },
+}
+
+impl FenError {
+    pub fn get_column(&self) -> usize {
+        match *self {
+            FenError::MissingField { column, .. }
+            | FenError::TooManyFields { column }
+            | FenError::BadRankCount { column, .. }
+            | FenError::BadRankLength { column, .. }
+            | FenError::UnknownPieceLetter { column, .. }
+            | FenError::MissingKing { column, .. }
+            | FenError::TooManyKings { column, .. }
+            | FenError::PawnOnBackRank { column }
+            | FenError::BadSideToMove { column }
+            | FenError::BadCastlingLetter { column, .. }
+            | FenError::ImpossibleCastlingRights { column, .. }
+            | FenError::BadEnPassantSquare { column }
+            | FenError::BadMoveCounter { column, .. } => column,
+        }
+    }
+}
+
+fn colour_name(white: bool) -> &'static str {
+    if white { "white" } else { "black" }
+}
+
+impl fmt::Display for FenError {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        match *self {
+            FenError::MissingField { field, column } =>
+                write!(f, "field {} is missing (column {})", field, column),
+            FenError::TooManyFields { column } =>
+                write!(f, "unexpected field after the fullmove number (column {})", column),
+            FenError::BadRankCount { ranks, column } =>
+                write!(f, "placement has {} ranks instead of 8 (column {})", ranks, column),
+            FenError::BadRankLength { rank, squares, column } =>
+                write!(f, "rank {} covers {} squares instead of 8 (column {})", rank, squares, column),
+            FenError::UnknownPieceLetter { letter, column } =>
+                write!(f, "unknown piece letter '{}' (column {})", letter, column),
+            FenError::MissingKing { white, column } =>
+                write!(f, "there is no {} king (column {})", colour_name(white), column),
+            FenError::TooManyKings { white, column } =>
+                write!(f, "there is more than one {} king (column {})", colour_name(white), column),
+            FenError::PawnOnBackRank { column } =>
+                write!(f, "pawn on the first or last rank (column {})", column),
+            FenError::BadSideToMove { column } =>
+                write!(f, "side to move must be 'w' or 'b' (column {})", column),
+            FenError::BadCastlingLetter { letter, column } =>
+                write!(f, "unexpected castling letter '{}' (column {})", letter, column),
+            FenError::ImpossibleCastlingRights { letter, column } =>
+                write!(f, "castling right '{}' needs the king and rook on their starting squares (column {})", letter, column),
+            FenError::BadEnPassantSquare { column } =>
+                write!(f, "impossible en passant square (column {})", column),
+            FenError::BadMoveCounter { field, column } =>
+                write!(f, "field {} is not a valid move counter (column {})", field, column),
+        }
+    }
+}
+
+impl Error for FenError {}
+
 impl Fen {
-    pub(crate) fn interpret(&self) -> Vec<Tile> {
-        let mut new_tiles = vec![];
+    // Reads the six space separated fields: piece placement, side to move,
+    // castling availability, en passant square, halfmove clock and fullmove
+    // number. Missing trailing fields keep the defaults from Position::empty,
+    // so a bare placement still loads.
+    pub fn interpret(&self) -> Position {
+        let mut position = Position::empty();
+        let mut fields = self.fen_string.split_whitespace();
+
+        if let Some(placement) = fields.next() {
+            self.interpret_placement(placement, &mut position);
+        }
+
+        if let Some(side_to_move) = fields.next() {
+            position.white_to_move = side_to_move != "b";
+        }
+
+        if let Some(castling) = fields.next() {
+            position.castling_rights = CastlingRights {
+                white_kingside: castling.contains('K'),
+                white_queenside: castling.contains('Q'),
+                black_kingside: castling.contains('k'),
+                black_queenside: castling.contains('q'),
+            };
+        }
+
+        if let Some(en_passant) = fields.next() {
+            position.en_passant = Square::from_algebraic(en_passant).map(|square| square.index());
+        }
+
+        if let Some(halfmove_clock) = fields.next().and_then(|field| field.parse().ok()) {
+            position.halfmove_clock = halfmove_clock;
+        }
+
+        if let Some(fullmove_number) = fields.next().and_then(|field| field.parse().ok()) {
+            position.fullmove_number = fullmove_number;
+        }
+
+        // The starting position counts towards repetitions too
+        let position_hash = position.get_position_hash();
+        position.position_history.push(position_hash);
+
+        position
+    }
+
+    // Like interpret, but rejects anything that doesn't describe a legal
+    // setup. The halfmove clock and fullmove number may be left out.
+    pub fn parse(&self) -> Result<Position, FenError> {
+        let fields = self.split_fields();
+
+        if fields.len() < 4 {
+            return Err(FenError::MissingField { field: fields.len() + 1, column: self.fen_string.chars().count() + 1 });
+        }
+
+        if fields.len() > 6 {
+            return Err(FenError::TooManyFields { column: fields[6].0 });
+        }
+
+        let mut position = Position::empty();
+
+        self.parse_placement(fields[0], &mut position)?;
+
+        position.white_to_move = match fields[1].1 {
+            "w" => true,
+            "b" => false,
+            _ => return Err(FenError::BadSideToMove { column: fields[1].0 }),
+        };
+
+        position.castling_rights = self.parse_castling(fields[2], &position)?;
+        position.en_passant = self.parse_en_passant(fields[3], &position)?;
+
+        if let Some(&(column, halfmove_clock)) = fields.get(4) {
+            position.halfmove_clock = halfmove_clock.parse()
+                .map_err(|_| FenError::BadMoveCounter { field: 5, column })?;
+        }
+
+        if let Some(&(column, fullmove_number)) = fields.get(5) {
+            position.fullmove_number = match fullmove_number.parse() {
+                Ok(number) if number > 0 => number,
+                _ => return Err(FenError::BadMoveCounter { field: 6, column }),
+            };
+        }
+
+        let position_hash = position.get_position_hash();
+        position.position_history.push(position_hash);
+
+        Ok(position)
+    }
+
+    // The whitespace separated fields, each with the column it starts at
+    fn split_fields(&self) -> Vec<(usize, &str)> {
+        let mut fields: Vec<(usize, &str)> = vec![];
+        let mut start: Option<usize> = None;
+
+        for (column, (offset, c)) in self.fen_string.char_indices().enumerate() {
+            match (start, c.is_whitespace()) {
+                (None, false) => start = Some(offset),
+                (Some(start_offset), true) => {
+                    let field = &self.fen_string[start_offset..offset];
+
+                    fields.push((column + 1 - field.chars().count(), field));
+                    start = None;
+                }
+                _ => {}
+            }
+        }
+
+        if let Some(start_offset) = start {
+            let field = &self.fen_string[start_offset..];
+
+            fields.push((self.fen_string.chars().count() + 1 - field.chars().count(), field));
+        }
+
+        fields
+    }
+
+    fn parse_placement(&self, (start, placement): (usize, &str), position: &mut Position) -> Result<(), FenError> {
         let piece_hashmap = self.load_hashmap_for_pieces();
+        let ranks: Vec<&str> = placement.split('/').collect();
 
-        let mut i = 0;
-        let mut row = 1;
-        let mut board_pos = 0;
+        if ranks.len() != 8 {
+            return Err(FenError::BadRankCount { ranks: ranks.len(), column: start });
+        }
 
-        for c in self.fen_string.chars() {
-            if c.is_digit(10) {
-                let spaces = c as i32 - 0x30;
+        let mut rank_column = start;
 
-                for _slot in 0..spaces {
-                    new_tiles.push(self.create_blank_tile(row, i, board_pos));
+        for (row, rank) in ranks.iter().enumerate() {
+            let mut squares: u32 = 0;
 
-                    i += 1;
-                    board_pos += 1;
+            for (offset, c) in rank.chars().enumerate() {
+                let column = rank_column + offset;
+
+                if ('1'..='8').contains(&c) {
+                    squares += c as u32 - 0x30;
+                    continue;
+                }
+
+                let piece = match piece_hashmap.get(&c) {
+                    Some(piece) => piece,
+                    None => return Err(FenError::UnknownPieceLetter { letter: c, column }),
+                };
+
+                if piece.name == "Pawn" && (row == 0 || row == 7) {
+                    return Err(FenError::PawnOnBackRank { column });
                 }
+
+                if squares < 8 {
+                    position.put_piece(row as u32 * 8 + squares, piece);
+                }
+
+                squares += 1;
+            }
+
+            if squares != 8 {
+                return Err(FenError::BadRankLength { rank: 8 - row as u32, squares, column: rank_column });
+            }
+
+            rank_column += rank.chars().count() + 1;
+        }
+
+        for &white in [true, false].iter() {
+            match position.get_piece_bitboard(white, KING).count_ones() {
+                0 => return Err(FenError::MissingKing { white, column: start }),
+                1 => {}
+                _ => return Err(FenError::TooManyKings { white, column: start }),
+            }
+        }
+
+        Ok(())
+    }
+
+    // A right is only possible while the king is on its e-file square and the
+    // rook in the matching corner
+    fn parse_castling(&self, (start, castling): (usize, &str), position: &Position) -> Result<CastlingRights, FenError> {
+        let mut rights = CastlingRights {
+            white_kingside: false,
+            white_queenside: false,
+            black_kingside: false,
+            black_queenside: false,
+        };
+
+        if castling == "-" {
+            return Ok(rights);
+        }
+
+        for (offset, letter) in castling.chars().enumerate() {
+            let column = start + offset;
+
+            let (white, kingside, king_index, rook_index) = match letter {
+                'K' => (true, true, 60, 63),
+                'Q' => (true, false, 60, 56),
+                'k' => (false, true, 4, 7),
+                'q' => (false, false, 4, 0),
+                _ => return Err(FenError::BadCastlingLetter { letter, column }),
+            };
+
+            // Each letter may only appear once
+            if rights.allows(white, kingside) {
+                return Err(FenError::BadCastlingLetter { letter, column });
+            }
+
+            if position.get_piece_bitboard(white, KING) & square_bit(king_index) == 0
+                || position.get_piece_bitboard(white, ROOK) & square_bit(rook_index) == 0 {
+                return Err(FenError::ImpossibleCastlingRights { letter, column });
+            }
+
+            match letter {
+                'K' => rights.white_kingside = true,
+                'Q' => rights.white_queenside = true,
+                'k' => rights.black_kingside = true,
+                _ => rights.black_queenside = true,
+            }
+        }
+
+        Ok(rights)
+    }
+
+    // The square has to be the one just skipped by a double push of the side
+    // that isn't to move: empty, with that pawn in front of it and the square
+    // the pawn came from empty too
+    fn parse_en_passant(&self, (column, en_passant): (usize, &str), position: &Position) -> Result<Option<u32>, FenError> {
+        if en_passant == "-" {
+            return Ok(None);
+        }
+
+        let target = match Square::from_algebraic(en_passant) {
+            Some(target) => target.index(),
+            None => return Err(FenError::BadEnPassantSquare { column }),
+        };
+
+        // White pawns move towards row 0, so a white double push skips row 5
+        let pushed_white = !position.white_to_move;
+        let skipped_row = if pushed_white { 5 } else { 2 };
+
+        if target / 8 != skipped_row {
+            return Err(FenError::BadEnPassantSquare { column });
+        }
+
+        let (pawn_index, origin_index) = if pushed_white { (target - 8, target + 8) } else { (target + 8, target - 8) };
+        let occupancy = position.get_all_occupancy();
+
+        if position.get_piece_bitboard(pushed_white, PAWN) & square_bit(pawn_index) == 0
+            || occupancy & (square_bit(target) | square_bit(origin_index)) != 0 {
+            return Err(FenError::BadEnPassantSquare { column });
+        }
+
+        Ok(Some(target))
+    }
+
+    fn interpret_placement(&self, placement: &str, position: &mut Position) {
+        let piece_hashmap = self.load_hashmap_for_pieces();
+
+        let mut board_pos = 0;
+
+        for c in placement.chars() {
+            if c.is_ascii_digit() {
+                let spaces = c as u32 - 0x30;
+
+                board_pos += spaces;
             } else if c == '/' {
-                row += 1;
-                i = 0;
+                continue;
             } else {
-                let found_piece = piece_hashmap.get(&c);
-
-                if found_piece.is_none() {
-                    new_tiles.push(self.create_blank_tile(row, i, board_pos));
-                } else {
-                    new_tiles.push(self.create_pieced_tile(row, i, found_piece.unwrap().clone(), board_pos))
+                if let Some(found_piece) = piece_hashmap.get(&c) {
+                    if board_pos < 64 {
+                        position.put_piece(board_pos, found_piece);
+                    }
                 }
 
-                i += 1;
                 board_pos += 1;
             }
         }
-
-        return new_tiles
     }
 
     fn load_hashmap_for_pieces(&self) -> HashMap<char, Piece> {
         let mut chars_to_piece: HashMap<char, Piece> = HashMap::new();
 
-        // Black pieces
-        chars_to_piece.insert('R', self.create_generic_piece("Rook", 5, false, true));
-        chars_to_piece.insert('K', self.create_generic_piece("King", 0, false, false));
-        chars_to_piece.insert('B', self.create_generic_piece("Bishop", 2, false, true));
-        chars_to_piece.insert('P', self.create_generic_piece("Pawn", 1, false, true));
-        chars_to_piece.insert('N', self.create_generic_piece("Knight", 1, false, true));
-        chars_to_piece.insert('Q', self.create_generic_piece("Queen", 1, false, true));
-
         // White pieces
-        chars_to_piece.insert('r', self.create_generic_piece("Rook", 5, true, true));
-        chars_to_piece.insert('k', self.create_generic_piece("King", 0, true, false));
-        chars_to_piece.insert('b', self.create_generic_piece("Bishop", 2, true, true));
-        chars_to_piece.insert('p', self.create_generic_piece("Pawn", 1, true, true));
-        chars_to_piece.insert('n', self.create_generic_piece("Knight", 1, true, true));
-        chars_to_piece.insert('q', self.create_generic_piece("Queen", 1, true, true));
+        chars_to_piece.insert('R', Piece::new("Rook", true));
+        chars_to_piece.insert('K', Piece::new("King", true));
+        chars_to_piece.insert('B', Piece::new("Bishop", true));
+        chars_to_piece.insert('P', Piece::new("Pawn", true));
+        chars_to_piece.insert('N', Piece::new("Knight", true));
+        chars_to_piece.insert('Q', Piece::new("Queen", true));
+
+        // Black pieces
+        chars_to_piece.insert('r', Piece::new("Rook", false));
+        chars_to_piece.insert('k', Piece::new("King", false));
+        chars_to_piece.insert('b', Piece::new("Bishop", false));
+        chars_to_piece.insert('p', Piece::new("Pawn", false));
+        chars_to_piece.insert('n', Piece::new("Knight", false));
+        chars_to_piece.insert('q', Piece::new("Queen", false));
         
-        return chars_to_piece
+        chars_to_piece
     }
+}
+
+// The letter load_hashmap_for_pieces reads back as this piece
+fn get_letter_for_piece(piece: &Piece) -> char {
+    let letter = match piece.name.as_str() {
+        "Pawn" => 'p',
+        "Knight" => 'n',
+        "Bishop" => 'b',
+        "Rook" => 'r',
+        "Queen" => 'q',
+        _ => 'k',
+    };
+
+    if piece.white { letter.to_ascii_uppercase() } else { letter }
+}
+
+impl Position {
+    // All six fields, in the form Fen::parse reads back
+    pub fn to_fen(&self) -> String {
+        let mut ranks: Vec<String> = vec![];
 
-    fn create_blank_tile(&self, row: u32, i: u32, board_pos: u32) -> Tile {
-        return Tile {
-            color: if (row + i) % 2 != 0 { graphics::color::hex("ccac95") } else { graphics::color::hex("a67a5a") },
-            x1: i * 100,
-            y1: (row - 1) * 100,
-            x2: (i * 100) + 99,
-            y2: ((row - 1) * 100) + 99,
-            owning_piece: None,
-            board_index: board_pos
+        for row in 0..8 {
+            let mut rank = String::new();
+            let mut empty = 0;
+
+            for index in row * 8..row * 8 + 8 {
+                match self.piece_at(index) {
+                    Some(piece) => {
+                        if empty > 0 {
+                            rank.push_str(&empty.to_string());
+                            empty = 0;
+                        }
+
+                        rank.push(get_letter_for_piece(&piece));
+                    }
+                    None => empty += 1,
+                }
+            }
+
+            if empty > 0 {
+                rank.push_str(&empty.to_string());
+            }
+
+            ranks.push(rank);
         }
-    }
 
-    fn create_pieced_tile(&self, row: u32, i: u32, piece: Piece, board_pos: u32) -> Tile {
-        return Tile {
-            color: if (row + i) % 2 != 0 { graphics::color::hex("ccac95") } else { graphics::color::hex("a67a5a") },
-            x1: i * 100,
-            y1: (row - 1) * 100,
-            x2: (i * 100) + 99,
-            y2: ((row - 1) * 100) + 99,
-            owning_piece: Some(piece),
-            board_index: board_pos
+        let mut castling = String::new();
+
+        for &(letter, allowed) in [
+            ('K', self.castling_rights.white_kingside),
+            ('Q', self.castling_rights.white_queenside),
+            ('k', self.castling_rights.black_kingside),
+            ('q', self.castling_rights.black_queenside),
+        ].iter() {
+            if allowed {
+                castling.push(letter);
+            }
         }
-    }
 
-    fn create_generic_piece(&self, name_str: &str, piece_worth: i32, is_white: bool, can_be_captured: bool) -> Piece {
-        return Piece {
-            worth: piece_worth,
-            name: name_str.to_string(),
-            capturable: can_be_captured,
-            white: is_white
+        if castling.is_empty() {
+            castling.push('-');
         }
+
+        let en_passant = match self.en_passant {
+            Some(index) => Square::from_index(index).map(|square| square.to_algebraic()).unwrap_or_default(),
+            None => String::from("-"),
+        };
+
+        format!(
+            "{} {} {} {} {} {}",
+            ranks.join("/"),
+            if self.white_to_move { "w" } else { "b" },
+            castling,
+            en_passant,
+            self.halfmove_clock,
+            self.fullmove_number
+        )
+    }
+
+    // Writes to_fen to the file, followed by a newline
+    pub fn write_fen<P: AsRef<Path>>(&self, path: P) -> io::Result<()> {
+        fs::write(path, self.to_fen() + "\n")
     }
-}
\ No newline at end of file
+}
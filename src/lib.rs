@@ -0,0 +1,13 @@
+// Chess rules, FEN/EPD/PGN support and SAN/UCI move notation, with no
+// graphics dependencies. The piston board in main.rs is built on top of this.
+
+extern crate chrono;
+
+pub mod bitboard;
+pub mod square;
+pub mod position;
+pub mod fen;
+pub mod epd;
+pub mod san;
+pub mod uci;
+pub mod pgn;
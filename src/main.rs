@@ -1,17 +1,16 @@
 mod r#move;
-mod fen;
 mod timer;
 mod modules;
 
+extern crate chess_engine;
 extern crate glutin_window;
 extern crate graphics;
 extern crate opengl_graphics;
 extern crate piston;
 
-extern crate rand;
+extern crate chrono;
 
 use std::collections::HashMap;
-use std::num::Wrapping;
 use std::ops::Mul;
 use graphics::circle_arc;
 use piston::window::WindowSettings;
@@ -20,17 +19,36 @@ use piston::input::*;
 use glutin_window::GlutinWindow;
 use graphics::{DrawState, Ellipse, Image, Transformed};
 use opengl_graphics::{GlGraphics, GlyphCache, OpenGL, Texture, TextureSettings};
-use fen::Fen;
+use chess_engine::fen::{Fen, START_POSITION};
+use chess_engine::pgn::Game;
+use chrono::Local;
 use std::path::Path;
 use std::time::Instant;
 use modules::*;
+use chess_engine::position::*;
+use chess_engine::square::Square;
 use r#move::MoveHandler;
 use timer::Timer;
 
 
 const TILE_AXIS_PIXELS: f64 = 100.0;
+const SAVED_FEN_PATH: &str = "position.fen";
 
 fn main() {
+    let args: Vec<String> = std::env::args().collect();
+
+    // `chess-engine perft <depth> [fen]` counts the move tree instead of opening the board
+    if args.len() > 1 && args[1] == "perft" {
+        run_perft(&args[2..]);
+        return;
+    }
+
+    // Any other arguments are read as the FEN to start from
+    let position = match load_position(&args[1..]) {
+        Some(position) => position,
+        None => return,
+    };
+
     // Change this to OpenGL::V2_1 if this fails.
     let opengl = OpenGL::V3_2;
 
@@ -41,19 +59,22 @@ fn main() {
         .build()
         .unwrap();
 
+    let game = Game::new(&position);
+
     let mut board = Board {
         gl: GlGraphics::new(opengl),
         tiles_per_axis: 8,
         tile_len: TILE_AXIS_PIXELS,
+        flipped: false,
         tiles: HashMap::new(),
+        position,
+        result: None,
+        game,
+        pgn_path: Local::now().format("game-%Y%m%d-%H%M%S.pgn").to_string(),
     };
 
     let mut move_handler = MoveHandler::new();
 
-    let fen_manager = Fen {
-        fen_string: "RNBQKBNR/PPPPPPPP/8/8/8/8/pppppppp/rnbkqbnr".to_string()
-    };
-
     let mut events = Events::new(EventSettings::new())
         .ups(30);
 
@@ -74,19 +95,46 @@ fn main() {
     while let Some(e) = events.next(&mut window) {
         use graphics::*;
 
+        // Clocks stop once the game is decided
+        if board.result.is_some() {
+            timer_handler.started = false;
+        }
+
+        // The clocks start with the first move and follow the side to move
+        if !timer_handler.started && board.result.is_none() && !board.game.moves.is_empty() {
+            timer_handler.started = true;
+            timer_handler.last_tick = Instant::now();
+        }
+
+        timer_handler.white_turn = board.position.white_to_move;
+
         // Start the timer if the game has started
         if timer_handler.started {
             if Instant::now().duration_since(timer_handler.last_tick).as_secs() >= 1 {
                 timer_handler.last_tick = Instant::now();
 
                 if timer_handler.white_turn {
-                    timer_handler.white_time -= 1;
+                    timer_handler.white_time = timer_handler.white_time.saturating_sub(1);
                 } else {
-                    timer_handler.black_time -= 1;
+                    timer_handler.black_time = timer_handler.black_time.saturating_sub(1);
                 }
             }
         }
 
+        // F prints the current position as FEN, S also saves it to SAVED_FEN_PATH.
+        // P saves the game so far as PGN, which also happens when the game ends.
+        if let Some(Button::Keyboard(key)) = e.press_args() {
+            match key {
+                Key::F => println!("{}", board.position.to_fen()),
+                Key::S => match board.position.write_fen(SAVED_FEN_PATH) {
+                    Ok(()) => println!("Saved {} to {}", board.position.to_fen(), SAVED_FEN_PATH),
+                    Err(error) => println!("Could not save the position: {}", error),
+                },
+                Key::P => board.export_pgn(),
+                _ => {}
+            }
+        }
+
         move_handler.event(
             800.0,
             &e,
@@ -103,29 +151,23 @@ fn main() {
         if let Some(r) = e.render_args() {
             timer_handler.draw_timers(&mut board.gl, &r, &mut glyphs);
 
+            if let Some(ref result) = board.result {
+                timer_handler.draw_result(&mut board.gl, &r, &mut glyphs, result);
+            }
+
             if started == 0 {
-                board.render_fen(&r, &fen_manager);
+                board.render_layout(&r);
                 let tile = move_handler.get_tile_from_position("a1", &board);
                 if tile.is_some() {
                     println!("Tile was some: {}", tile.clone().unwrap().board_index)
                 }
 
-                let string_pos = move_handler.get_position_from_tile(&tile.clone().unwrap());
+                let string_pos = move_handler.get_position_from_tile(&board, &tile.clone().unwrap());
 
                 if string_pos.is_none() {
                     println!("String position is none");
                 }
 
-                let transformed = move_handler.get_position_from_transformations("a1", 1, 1);
-
-                if transformed.is_none() {
-                    println!("Was none");
-                } else {
-                    let unwrapped = transformed.unwrap();
-
-                    println!("New pos: {}", unwrapped);
-                }
-
                 println!("String pos: {}", string_pos.unwrap());
 
                 started = 1;
@@ -138,13 +180,65 @@ fn main() {
                         &r
                     );
                 }
+
+                move_handler.render_promotion_picker(&mut board, &r);
             }
         }
     }
 }
 
+// Prints the node count below each legal move, then the total. The FEN may
+// be passed as one quoted argument or as its separate fields.
+fn run_perft(args: &[String]) {
+    let depth = match args.first().and_then(|depth| depth.parse::<u32>().ok()) {
+        Some(depth) => depth,
+        None => {
+            println!("Usage: chess-engine perft <depth> [fen]");
+            return;
+        }
+    };
+
+    let mut position = match load_position(&args[1..]) {
+        Some(position) => position,
+        None => return,
+    };
+
+    let started = Instant::now();
+    let mut total: u64 = 0;
+
+    // No moves to list at depth 0, the position itself is the one node
+    if depth == 0 {
+        total = position.perft(depth);
+    }
+
+    for (chess_move, nodes) in position.divide(depth) {
+        println!("{}: {}", chess_move.to_uci(), nodes);
+        total += nodes;
+    }
+
+    println!();
+    println!("Nodes searched: {}", total);
+    println!("Time: {} ms", started.elapsed().as_millis());
+}
+
+// The start position when no FEN is given. A FEN from the command line is
+// validated and None is returned, after printing why, if it doesn't parse.
+fn load_position(fen_args: &[String]) -> Option<Position> {
+    let fen_manager = Fen {
+        fen_string: if fen_args.is_empty() { START_POSITION.to_string() } else { fen_args.join(" ") }
+    };
+
+    match fen_manager.parse() {
+        Ok(position) => Some(position),
+        Err(error) => {
+            println!("Invalid FEN: {}", error);
+            None
+        }
+    }
+}
+
 impl Tile {
-    fn render(&mut self, gl: &mut GlGraphics, args: &RenderArgs) {
+    fn render(&self, gl: &mut GlGraphics, args: &RenderArgs, owning_piece: Option<&Piece>) {
         use graphics;
 
         let square = graphics::rectangle::square(self.x1 as f64, self.y1 as f64, 100.0);
@@ -162,8 +256,8 @@ impl Tile {
             },
         );
 
-        if self.owning_piece.is_some() {
-            let piece = self.owning_piece.clone().unwrap();
+        if owning_piece.is_some() {
+            let piece = owning_piece.unwrap();
 
             // Kind of cancerous lol.
             // Way it has to be done though
@@ -289,268 +383,46 @@ impl Tile {
 }
 
 impl Board {
-    fn render_fen(&mut self, args: &RenderArgs, fen: &Fen) {
-        use graphics;
-
-        for mut tile in fen.interpret() {
-            tile.render(&mut self.gl, args);
-            self.tiles.insert(tile.board_index, tile);
-        }
-    }
-
-    fn update(&mut self, args: &RenderArgs) {
-        use graphics;
-
-        for tile in self.tiles.values_mut() {
-            tile.render(&mut self.gl, args);
-        }
-    }
-    
-    fn get_horizontal_moves(&self, current_index: u32) -> Vec<Tile> {
-        let mut moves: Vec<Tile> = vec![];
-        let row = self.get_current_row_from_index(current_index).unwrap();
-
-        let row_amount = Wrapping(8);
-        let maximum_index = row_amount * Wrapping(row);
-        
-        let corrected_minimum_index = Wrapping(row) - Wrapping(1);
-        let minimum_index = row_amount * corrected_minimum_index;
-
-        for i in 0..9 {
-            let addition = Wrapping(i);
-
-            let position_left = Wrapping(current_index) - addition;
-            let position_right = Wrapping(current_index) + addition;
-
-            let optional_vertical_tile_left = self.get_tile_based_on_index(position_left.0);
-            
-            if optional_vertical_tile_left.is_some() {
-                if position_left.0 < minimum_index.0 {
-                    continue;
-                }
-
-                moves.push(optional_vertical_tile_left.unwrap());
-            }
-
-            let optional_vertical_tile_right = self.get_tile_based_on_index(position_right.0);
-
-            if optional_vertical_tile_right.is_some() {
-                if position_right.0 > maximum_index.0 {
-                    continue;
-                }
-
-                moves.push(optional_vertical_tile_right.unwrap());
-            }
-        }
-
-        return moves;
-    }
-
-    fn get_vertical_moves(&self, current_index: u32) -> Vec<Tile> {
-        let mut moves: Vec<Tile> = vec![];
-
-        for i in 0..9 {
-            let addition = Wrapping(8 * i);
-
-            let position_upwards = Wrapping(current_index) + addition;
-            let position_downwards = Wrapping(current_index) - addition;
-
-            let optional_vertical_tile_downwards = self.get_tile_based_on_index(position_downwards.0);
-            
-
-            if optional_vertical_tile_downwards.is_some() {
-                moves.push(optional_vertical_tile_downwards.unwrap());
-            }
-
-            let optional_vertical_tile_upwards = self.get_tile_based_on_index(position_upwards.0);
-
-            if optional_vertical_tile_upwards.is_some() {
-                moves.push(optional_vertical_tile_upwards.unwrap());
-            }
-        }
-
-        return moves;
-    }
-
-    pub fn get_current_row_from_index(&self, position: u32) -> Option<u32> {
-        return Some((position).div_ceil(8));
-    }
-
-    fn get_tile_based_on_piece(&self, piece: &Piece) -> Option<&Tile> {
-        for tile in self.tiles.values() {
-            if tile.owning_piece.is_some() && tile.owning_piece.clone().unwrap() == *piece {
-                return Some(tile);
-            }
-        }
-
-        return None;
-    }
-
-    fn get_tile_based_on_index(&self, index: u32) -> Option<Tile> {
-        for tile in self.tiles.values() {
-            if tile.board_index == index {
-                return Some(tile.clone());
-            }
-        }
-
-        return None;
-    }
-
-    fn render_no_fen(&mut self, args: &RenderArgs) {
-        use graphics;
-
+    // Lays out the 8x8 tiles and draws them with the pieces from the position
+    fn render_layout(&mut self, args: &RenderArgs) {
         // Our custom colors b/c they look pretty
         let white = graphics::color::hex("ccac95");
         let black = graphics::color::hex("a67a5a");
 
-        // Track board position (0-63)
-        let mut board_pos = 0;
+        for board_pos in 0..64 {
+            let square = Square::from_index(board_pos).unwrap();
+            let (x, y) = square.to_pixel(self.tile_len, self.flipped);
 
-        for file in 0..8 {
-            for rank in 0..8 {
-                let is_light: bool = (file + rank) % 2 != 0;
+            // a1 is dark, so light squares have an odd file + rank
+            let is_light: bool = (square.file() + square.rank()) % 2 == 1;
 
-                let mut tile = Tile {
-                    color: if is_light { white } else { black },
-                    x1: rank * 100,
-                    y1: file * 100,
-                    x2: (rank * 100) + 99,
-                    y2: (file * 100) + 99,
-                    owning_piece: None,
-                    board_index: board_pos,
-                };
+            let tile = Tile {
+                color: if is_light { white } else { black },
+                x1: x as u32,
+                y1: y as u32,
+                x2: x as u32 + 99,
+                y2: y as u32 + 99,
+                board_index: board_pos,
+            };
 
-                board_pos += 1;
-                tile.render(&mut self.gl, args);
+            tile.render(&mut self.gl, args, self.position.piece_at(tile.board_index).as_ref());
 
-                self.tiles.insert(tile.board_index, tile);
-            }
+            self.tiles.insert(tile.board_index, tile);
         }
     }
-}
-
-impl Piece {
-    fn get_move_tiles(&self, board: &Board, current_tile_index: u32) -> Vec<Tile> {
-        let mut moves: Vec<Tile> = vec![];
-        let current_tile = board.get_tile_based_on_index(current_tile_index);
-
-        if current_tile.is_some() {
-            let piece = current_tile.clone().unwrap().owning_piece;
-
-            if piece.is_some() {
-                let unwrapped_piece = piece.unwrap();
-                let unwrapped_tile = current_tile.clone().unwrap();
-                let name = unwrapped_piece.name;
-
-                // Pawn Logic
-                if name == "Pawn" {
-                    if !unwrapped_piece.white {
-                        // First tile for pawn
-                        let first_tile_down_i = unwrapped_tile.board_index + 16;
-                        let to_render = board.get_tile_based_on_index(first_tile_down_i);
-
-                        if to_render.is_some() {
-                            // Only start adding stuff if the owning piece is none.
-                            if to_render.clone().unwrap().owning_piece.is_none() {
-                                moves.push(to_render.unwrap());
-                            }
-                        }
-
-                        // Second tile if it is the first move
-                        let second_tile_down_i = unwrapped_tile.board_index + 8;
-                        let second_tile = board.get_tile_based_on_index(second_tile_down_i);
-
-                        if second_tile.is_some() {
-                            // Only start adding stuff if the owning piece is none.
-                            if second_tile.clone().unwrap().owning_piece.is_none() {
-                                moves.push(second_tile.unwrap());
-                            }
-                        }
-                    } else {
-                        // First tile for pawn
-                        let first_tile_down_i = unwrapped_tile.board_index - 16;
-                        let to_render = board.get_tile_based_on_index(first_tile_down_i);
-
-                        if to_render.is_some() {
-                            // Only start adding stuff if the owning piece is none.
-                            if to_render.clone().unwrap().owning_piece.is_none() {
-                                moves.push(to_render.unwrap());
-                            }
-                        }
-
-                        // Second tile if it is the first move
-                        let second_tile_down_i = unwrapped_tile.board_index - 8;
-                        let second_tile = board.get_tile_based_on_index(second_tile_down_i);
-
-                        if second_tile.is_some() {
-                            // Only start adding stuff if the owning piece is none.
-                            if second_tile.clone().unwrap().owning_piece.is_none() {
-                                moves.push(second_tile.unwrap());
-                            }
-                        }
-                    }
-                }
-
-                // Bishop Logic
-                if name == "Bishop" {
-                    if !unwrapped_piece.white {
-                        for i in 0..9 {
-                            let position = Wrapping((8 * i) + (1 * i));
-                            let position_backwards = Wrapping((8 * i) + (1 * i));
-
-                            let diagonal = Wrapping(unwrapped_tile.board_index) + position;
-                            let backwards_diagonal = Wrapping(unwrapped_tile.board_index) - position_backwards;
-                            let max_distance_allowed = 8 - (unwrapped_tile.x1 / 100);
-
-                            if i > max_distance_allowed {
-                                continue;
-                            }
-
-
-                            if diagonal.0 > 63 {
-                                continue;
-                            }
-
-
-                            let optional_diag_tile = board.get_tile_based_on_index(diagonal.0);
 
-                            if optional_diag_tile.is_some() {
-                                moves.push(optional_diag_tile.unwrap());
-                            }
-
-                            if backwards_diagonal.0 > 63 {
-                                continue;
-                            }
-
-                            let optional_backwards_diag_tile = board.get_tile_based_on_index(backwards_diagonal.0);
-
-                            if optional_backwards_diag_tile.is_some() {
-                                moves.push(optional_backwards_diag_tile.unwrap())
-                            }
-                        }
-                    } else {
-
-                    }
-                }
-
-                // Logic for Rooks
-                if name == "Rook" {
-                    let vertical_moves = board.get_vertical_moves(current_tile_index);
-
-                    for tile in vertical_moves {
-                        moves.push(tile);
-                    }
-
-                    let horizontal_moves = board.get_horizontal_moves(current_tile_index);
-
-                    for tile in horizontal_moves {
-                        moves.push(tile);
-                    }
-                }
-            }
+    // Writes the game to pgn_path, replacing any earlier export of it
+    fn export_pgn(&self) {
+        match self.game.write_pgn(&self.pgn_path) {
+            Ok(()) => println!("Saved the game to {}", self.pgn_path),
+            Err(error) => println!("Could not save the game: {}", error),
         }
+    }
 
-        return moves;
+    fn update(&mut self, args: &RenderArgs) {
+        for tile in self.tiles.values() {
+            tile.render(&mut self.gl, args, self.position.piece_at(tile.board_index).as_ref());
+        }
     }
 }
 
@@ -1,22 +1,23 @@
 use std::collections::HashMap;
 use graphics::types::Color;
 use opengl_graphics::GlGraphics;
-
-#[derive(Clone)]
-#[derive(PartialEq)]
-#[derive(Debug)]
-pub struct Piece {
-    pub worth: i32,
-    pub name: String,
-    pub capturable: bool,
-    pub white: bool
-}
+use chess_engine::pgn::Game;
+use chess_engine::position::{GameResult, Position};
 
 pub struct Board {
     pub gl: GlGraphics,
     pub tiles_per_axis: u32,
     pub tile_len: f64,
+    // Black at the bottom instead of white
+    pub flipped: bool,
     pub tiles: HashMap<u32, Tile>,
+    // The game itself, the tiles only describe where each square is drawn
+    pub position: Position,
+    // Set once the game is over, after which no more moves are accepted
+    pub result: Option<GameResult>,
+    // Tags and moves for the PGN export, and the file it is written to
+    pub game: Game,
+    pub pgn_path: String,
 }
 
 #[derive(Clone)]
@@ -28,7 +29,6 @@ pub struct Tile {
     pub y1: u32,
     pub x2: u32,
     pub y2: u32,
-    pub owning_piece: Option<Piece>,
     pub board_index: u32,
 }
 
@@ -2,15 +2,26 @@ use std::num::Wrapping;
 
 use opengl_graphics::GlGraphics;
 use piston::input::{GenericEvent, RenderArgs};
+use graphics::{DrawState, Image, Rectangle};
+use chess_engine::bitboard::PIECE_NAMES;
+use chess_engine::position::{Move, PROMOTION_CHOICES};
+use chess_engine::square::Square;
 use ::{Tile};
 use Board;
 
 pub struct MoveHandler {
     pub selected_cell: Option<Tile>,
     pub cursor_pos: [f64; 2],
-    pub move_circle_tiles: Vec<Tile>
+    pub move_circle_tiles: Vec<Tile>,
+    // One of the promotion moves for the chosen tile, until a piece is picked
+    pub pending_promotion: Option<Move>
 }
 
+// Promotion picker squares, stacked in the side panel between the two clocks
+const PICKER_X: f64 = 860.0;
+const PICKER_Y: f64 = 220.0;
+const PICKER_TILE: f64 = 80.0;
+
 // For future refrence:
 // Any 'x' related aspects of the movement
 // system are related to the characters on a chessboard
@@ -20,123 +31,111 @@ pub struct MoveHandler {
 // system are related to the digits on a chessboard
 // (1-8)
 impl MoveHandler {
-    pub fn get_position_from_transformations(&self, start: &str, next_file: u32, next_rank: u32) -> Option<String> {
-        let alphabet: String = String::from("abcdefghijklmnopqrstuvwxyz");
-
-        let mut rank: u32 = 0;
-        let mut file: u32 = 0;
-
-        // Alphabetized character
-        let first_character: char = start.chars().nth(0).unwrap();
-
-        for (i, c) in alphabet.char_indices() {
-            if c == first_character {
-                file = i as u32
-            }
-        }
-
-        // File index
-        let second_character: char = start.chars().nth(1).unwrap();
-
-        if second_character.is_digit(10) {
-            let parsed_integer = second_character as i32 - 0x30;
-
-            if parsed_integer < 10 {
-                rank = (parsed_integer - 1) as u32;
-            }
-        }
-
-        let new_file = (file + (next_file + 1)).to_string();
-        let new_rank = alphabet.chars().nth((rank + next_rank) as usize);
-
-        if new_rank.is_none() {
-            return None;
-        }
-
-        return Some(String::from(new_rank.unwrap()) + new_file.as_str());
-    }
-
     pub fn move_piece_from_tile(&self, board: &mut Board, tile: &mut Tile, dist_x: u32, dist_y: u32) {
-        board.tiles.insert(tile.board_index, Tile {
-            color: tile.color,
-            x1: tile.x1,
-            y1: tile.y1,
-            x2: tile.x2,
-            y2: tile.y2,
-            owning_piece: None,
-            board_index: tile.board_index,
-        });
+        board.position.remove_piece(tile.board_index);
 
         return ();
     }
 
-    pub fn get_position_from_tile(&self, tile: &Tile) -> Option<String> {
-        let alphabet: String = String::from("abcdefghijklmnopqrstuvwxyz");
-        let x = tile.x1;
-        let y = tile.y1;
+    // Algebraic name of the square a tile is drawn for, e.g. "e4"
+    pub fn get_position_from_tile(&self, board: &Board, tile: &Tile) -> Option<String> {
+        Square::from_pixel(tile.x1 as f64, tile.y1 as f64, board.tile_len, board.flipped)
+            .map(|square| square.to_algebraic())
+    }
 
-        if x % 100 != 0 {
-            return None;
-        }
+    pub fn get_tile_from_position(&self, position: &str, board: &Board) -> Option<Tile> {
+        Square::from_algebraic(position).and_then(|square| board.tiles.get(&square.index()).cloned())
+    }
 
-        let alphabet_char = alphabet.chars().nth((x / 100) as usize);
+    pub fn commit_move(&self, board: &mut Board, chess_move: Move) {
+        // The game records the SAN, which depends on the position before the move
+        board.game.record_move(&board.position, chess_move);
+        board.position.make_move(chess_move);
+        board.result = board.position.get_game_result();
 
-        if alphabet_char.is_none() {
-            return None;
-        }
+        println!("Performed {} ({})", board.game.moves.last().unwrap().san, chess_move.to_uci());
 
-        let mut y_rank = y / 100;
+        if let Some(ref result) = board.result {
+            println!("Game over: {} ({})", result.headline(), result.reason());
 
-        if y_rank == 0 {
-            y_rank = 1;
+            board.game.set_result(Some(result));
+            board.export_pgn();
         }
-
-        let binding = y_rank.to_string();
-        let rank_string = binding.as_str();
-        let alphabet_string = String::from(alphabet_char.unwrap());
-
-        return Some(alphabet_string + rank_string);
     }
 
-    pub fn get_tile_from_position(&self, position: &str, board: &Board) -> Option<Tile> {
-        let alphabet: String = String::from("abcdefghijklmnopqrstuvwxyz");
-        let mut rank: u32 = 0;
-        let mut file: u32 = 0;
-
-        // Alphabetized character
-        let first_character: char = position.chars().nth(0).unwrap();
+    fn handle_promotion_click(&mut self, board: &mut Board, x: f64, y: f64) {
+        let pending = match self.pending_promotion.take() {
+            Some(pending) => pending,
+            None => return,
+        };
+
+        // Clicking anywhere outside the picker cancels the move
+        for (i, name) in PROMOTION_CHOICES.iter().enumerate() {
+            let top = PICKER_Y + (i as f64) * PICKER_TILE;
+
+            if x > PICKER_X && x < PICKER_X + PICKER_TILE && y > top && y < top + PICKER_TILE {
+                let chosen = board.position.get_legal_moves_from(pending.get_from())
+                    .into_iter()
+                    .find(|chess_move| {
+                        chess_move.get_to() == pending.get_to()
+                            && chess_move.get_promotion().map(|kind| PIECE_NAMES[kind]) == Some(*name)
+                    });
+
+                if let Some(chess_move) = chosen {
+                    self.commit_move(board, chess_move);
+                    println!("Promoted on tile {} to a {}", chess_move.get_to(), name);
+                }
 
-        for (i, c) in alphabet.char_indices() {
-            if c == first_character {
-                file = i as u32
+                break;
             }
         }
 
-        // File index
-        let second_character: char = position.chars().nth(1).unwrap();
-
-        if second_character.is_digit(10) {
-            let parsed_integer = second_character as i32 - 0x30;
+        self.selected_cell = None;
+        self.move_circle_tiles.clear();
+    }
 
-            if parsed_integer < 10 {
-                rank = (parsed_integer - 1) as u32;
+    pub fn render_promotion_picker(&self, board: &mut Board, args: &RenderArgs) {
+        let pending = match self.pending_promotion {
+            Some(pending) => pending,
+            None => return,
+        };
+
+        let tile = match board.tiles.get(&pending.get_to()) {
+            Some(tile) => tile.clone(),
+            None => return,
+        };
+
+        let white = board.position.piece_at(pending.get_from())
+            .map(|piece| piece.white)
+            .unwrap_or(true);
+
+        let textures = tile.map_textures_to_pieces();
+        let colour_prefix = if white { "White" } else { "Black" };
+
+        board.gl.draw(args.viewport(), |c, gl| {
+            for (i, name) in PROMOTION_CHOICES.iter().enumerate() {
+                let top = PICKER_Y + (i as f64) * PICKER_TILE;
+                let square = graphics::rectangle::square(PICKER_X, top, PICKER_TILE);
+
+                Rectangle::new(graphics::color::hex("ccac95"))
+                    .border(graphics::rectangle::Border { color: [0.0, 0.0, 0.0, 1.0], radius: 1.0 })
+                    .draw(square, &c.draw_state, c.transform, gl);
+
+                if let Some(texture) = textures.get(&(colour_prefix.to_owned() + name)) {
+                    Image::new()
+                        .rect([PICKER_X + 5.0, top + 5.0, PICKER_TILE - 10.0, PICKER_TILE - 10.0])
+                        .draw(texture, &DrawState::default(), c.transform, gl);
+                }
             }
-        }
-
-        let optional_tile = board.tiles.get(&(rank + file)).cloned();
-
-        if optional_tile.is_some() {
-            println!("Optional tile was some")
-        }
-
-        return optional_tile;
+        });
     }
 
     pub fn new() -> MoveHandler {
         MoveHandler {
             selected_cell: None,
             cursor_pos: [0.0; 2],
-            move_circle_tiles: vec![]
+            move_circle_tiles: vec![],
+            pending_promotion: None
         }
     }
 
@@ -147,42 +146,77 @@ impl MoveHandler {
         board: &mut Board,
         render_args: &RenderArgs
     ) {
-        use piston::input::{Button, MouseButton};
+        use piston::input::{Button, Key, MouseButton};
 
         if let Some(pos) = e.mouse_cursor_args() {
             self.cursor_pos = pos;
         }
 
+        // Backspace takes back the last move, even after the game has ended
+        if let Some(Button::Keyboard(Key::Backspace)) = e.press_args() {
+            if let Some(undone) = board.position.unmake_move() {
+                board.result = board.position.get_game_result();
+                board.game.undo_move();
+                board.game.set_result(board.result.as_ref());
+                self.selected_cell = None;
+                self.pending_promotion = None;
+                self.move_circle_tiles.clear();
+
+                println!("Took back {}", undone.to_uci());
+            }
+
+            return;
+        }
+
+        // The board is frozen once the game has a result
+        if board.result.is_some() {
+            return;
+        }
+
         if let Some(Button::Mouse(MouseButton::Left)) = e.press_args() {
             // Find coordinates relative to upper left corner.
             let x = self.cursor_pos[0];
             let y = self.cursor_pos[1];
 
+            if self.pending_promotion.is_some() {
+                self.handle_promotion_click(board, x, y);
+                return;
+            }
+
             if self.selected_cell == None {
                 // Check that coordinates are inside board boundaries.
                 if x >= 0.0 && x <= size && y >= 0.0 && y <= size {
                     // Compute the tile position.
-                    for tile in board.tiles.values().into_iter() {
+                    for tile in board.tiles.clone().values().into_iter() {
                         if tile.contained_inside(x as u32, y as u32) {
                             self.selected_cell = Option::from(tile.clone());
 
-                            let cloned_cell = self.selected_cell.clone();
+                            let piece = board.position.piece_at(tile.board_index);
 
-                            // Ensure optional for cell is validated
-                            if cloned_cell.is_some() {
-                                let piece = cloned_cell.clone().unwrap().owning_piece.clone();
+                            // Valid piece in cell
+                            if piece.is_none() {
+                                return;
+                            }
 
-                                // Valid piece in cell
-                                if piece.is_none() {
-                                    return;
-                                }
+                            // Only the side to move can pick up a piece
+                            if piece.unwrap().white != board.position.white_to_move {
+                                self.selected_cell = None;
+                                return;
+                            }
 
-                                let moves = piece.unwrap().get_move_tiles(board, cloned_cell.unwrap().board_index);
+                            for chess_move in board.position.get_legal_moves_from(tile.board_index) {
+                                let tile_move = match board.tiles.get(&chess_move.get_to()) {
+                                    Some(tile_move) => tile_move.clone(),
+                                    None => continue,
+                                };
 
-                                for tile_move in moves.iter() {
-                                    tile_move.render_move_circle(&mut board.gl, render_args);
-                                    self.move_circle_tiles.push(tile_move.clone());
+                                // Promotions give several moves to the same tile
+                                if self.move_circle_tiles.contains(&tile_move) {
+                                    continue;
                                 }
+
+                                tile_move.render_move_circle(&mut board.gl, render_args);
+                                self.move_circle_tiles.push(tile_move);
                             }
                         }
                     }
@@ -202,7 +236,7 @@ impl MoveHandler {
                             }
 
                             let unwrapped_prev_tile = prev_tile.clone().unwrap();
-                            let prev_piece = unwrapped_prev_tile.owning_piece;
+                            let prev_piece = board.position.piece_at(unwrapped_prev_tile.board_index);
 
                             if prev_piece.is_none() {
                                 self.selected_cell = None;
@@ -210,7 +244,7 @@ impl MoveHandler {
                                 return
                             }
 
-                            let this_piece = tile.clone().owning_piece;
+                            let this_piece = board.position.piece_at(tile.board_index);
                             let to_move = prev_piece.unwrap();
 
                             if this_piece.is_some() && (this_piece.unwrap().white == to_move.white) {
@@ -220,34 +254,27 @@ impl MoveHandler {
                                 return;
                             }
 
-                            if !to_move.get_move_tiles(&board, unwrapped_prev_tile.board_index).contains(tile) {
-                                println!("Could not move here because moves did not contain this tile");
-                                self.selected_cell = None;
-                                self.move_circle_tiles.clear();
-                                return
+                            let chess_move = board.position.get_legal_moves_from(unwrapped_prev_tile.board_index)
+                                .into_iter()
+                                .find(|chess_move| chess_move.get_to() == tile.board_index);
+
+                            let chess_move = match chess_move {
+                                Some(chess_move) => chess_move,
+                                None => {
+                                    println!("Could not move here because it is not a legal move");
+                                    self.selected_cell = None;
+                                    self.move_circle_tiles.clear();
+                                    return
+                                }
+                            };
+
+                            // A pawn reaching the last row waits for a piece to be picked
+                            if chess_move.get_promotion().is_some() {
+                                self.pending_promotion = Some(chess_move);
+                                break;
                             }
 
-                            board.tiles.insert(unwrapped_prev_tile.board_index, Tile {
-                                color: unwrapped_prev_tile.color,
-                                x1: unwrapped_prev_tile.x1,
-                                y1: unwrapped_prev_tile.y1,
-                                x2: unwrapped_prev_tile.x2,
-                                y2: unwrapped_prev_tile.y2,
-                                owning_piece: None,
-                                board_index: unwrapped_prev_tile.board_index,
-                            });
-
-                            board.tiles.insert(tile.board_index, Tile {
-                                color: tile.color,
-                                x1: tile.x1,
-                                y1: tile.y1,
-                                x2: tile.x2,
-                                y2: tile.y2,
-                                owning_piece: Some(to_move),
-                                board_index: tile.board_index,
-                            });
-
-                            println!("Performed a move from tile {} to tile {}", unwrapped_prev_tile.board_index, tile.board_index);
+                            self.commit_move(board, chess_move);
                         }
                     }
                 }
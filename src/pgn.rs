@@ -0,0 +1,765 @@
+// Portable Game Notation. A Game holds the tag pairs and a tree of moves:
+// the main line, with comments, NAGs and alternative lines hanging off each
+// move. Games are recorded while they are played and written out as PGN, or
+// read back from PGN files one game at a time with PgnReader.
+
+use std::error::Error;
+use std::fmt;
+use std::fs::{self, File};
+use std::io::{self, BufRead, BufReader};
+use std::path::Path;
+use chrono::Local;
+use fen::{Fen, FenError, START_POSITION};
+use position::{GameResult, Move, Position};
+use san::SanError;
+
+// The Seven Tag Roster, which every exported game starts with in this order
+pub const SEVEN_TAG_ROSTER: [&str; 7] = ["Event", "Site", "Date", "Round", "White", "Black", "Result"];
+
+// Movetext lines are kept below the 80 columns the PGN standard asks for
+const MAX_LINE_LENGTH: usize = 79;
+
+// One move of a line, with everything annotating it
+#[derive(Clone)]
+#[derive(Debug)]
+pub struct MoveNode {
+    pub san: String,
+    pub chess_move: Move,
+    // Numeric annotation glyphs, $1 being "!" and $2 "?"
+    pub nags: Vec<u32>,
+    // Comments in front of the move, only found at the start of a line
+    pub comments_before: Vec<String>,
+    pub comments: Vec<String>,
+    // Lines played instead of this move, from the same position
+    pub variations: Vec<Vec<MoveNode>>,
+}
+
+#[derive(Clone)]
+#[derive(Debug)]
+pub struct Game {
+    // Name and value of each tag, written in this order
+    pub tags: Vec<(String, String)>,
+    // The main line
+    pub moves: Vec<MoveNode>,
+    // Where the first move's number and colour come from
+    pub first_move_number: u32,
+    pub white_moves_first: bool,
+}
+
+// The result as written in the Result tag and at the end of the movetext
+pub fn get_result_token(result: Option<&GameResult>) -> &'static str {
+    match result {
+        Some(&GameResult::WhiteWins) => "1-0",
+        Some(&GameResult::BlackWins) => "0-1",
+        Some(&GameResult::Draw(_)) => "1/2-1/2",
+        None => "*",
+    }
+}
+
+impl Game {
+    // A game starting from `start`, dated today. Positions other than the
+    // standard start are recorded with the SetUp and FEN tags.
+    pub fn new(start: &Position) -> Game {
+        let mut game = Game {
+            tags: vec![],
+            moves: vec![],
+            first_move_number: start.fullmove_number,
+            white_moves_first: start.white_to_move,
+        };
+
+        game.set_tag("Event", "Casual game");
+        game.set_tag("Site", "?");
+        game.set_tag("Date", &Local::now().format("%Y.%m.%d").to_string());
+        game.set_tag("Round", "-");
+        game.set_tag("White", "?");
+        game.set_tag("Black", "?");
+        game.set_tag("Result", "*");
+
+        let fen = start.to_fen();
+
+        if fen != START_POSITION {
+            game.set_tag("SetUp", "1");
+            game.set_tag("FEN", &fen);
+        }
+
+        game
+    }
+
+    pub fn get_tag(&self, name: &str) -> Option<&str> {
+        self.tags.iter()
+            .find(|(tag_name, _)| tag_name == name)
+            .map(|(_, value)| value.as_str())
+    }
+
+    // Replaces the tag's value, or adds the tag at the end
+    pub fn set_tag(&mut self, name: &str, value: &str) {
+        match self.tags.iter_mut().find(|(tag_name, _)| tag_name == name) {
+            Some(tag) => tag.1 = value.to_string(),
+            None => self.tags.push((name.to_string(), value.to_string())),
+        }
+    }
+
+    // Call before the move is played, since SAN depends on the position it is played in
+    pub fn record_move(&mut self, position: &Position, chess_move: Move) {
+        self.moves.push(MoveNode::new(position.to_san(chess_move), chess_move));
+    }
+
+    pub fn undo_move(&mut self) {
+        self.moves.pop();
+    }
+
+    pub fn set_result(&mut self, result: Option<&GameResult>) {
+        self.set_tag("Result", get_result_token(result));
+    }
+
+    pub fn to_pgn(&self) -> String {
+        let mut pgn = String::new();
+
+        // The roster first, then any other tags in the order they were set
+        let roster = SEVEN_TAG_ROSTER.iter().map(|&name| (name, self.get_tag(name).unwrap_or("?")));
+        let others = self.tags.iter()
+            .filter(|(name, _)| !SEVEN_TAG_ROSTER.contains(&name.as_str()))
+            .map(|(name, value)| (name.as_str(), value.as_str()));
+
+        for (name, value) in roster.chain(others) {
+            let escaped = value.replace('\\', "\\\\").replace('"', "\\\"");
+
+            pgn.push_str(&format!("[{} \"{}\"]\n", name, escaped));
+        }
+
+        pgn.push('\n');
+        pgn.push_str(&self.get_movetext());
+        pgn.push('\n');
+
+        pgn
+    }
+
+    // Numbered SAN moves with their annotations, followed by the result and
+    // wrapped into lines
+    fn get_movetext(&self) -> String {
+        let mut tokens: Vec<String> = vec![];
+
+        push_line_tokens(&self.moves, self.first_move_number, self.white_moves_first, &mut tokens);
+        tokens.push(self.get_tag("Result").unwrap_or("*").to_string());
+
+        let mut lines: Vec<String> = vec![];
+        let mut line = String::new();
+
+        for token in tokens {
+            if !line.is_empty() && line.len() + 1 + token.len() > MAX_LINE_LENGTH {
+                lines.push(line);
+                line = String::new();
+            }
+
+            if !line.is_empty() {
+                line.push(' ');
+            }
+
+            line.push_str(&token);
+        }
+
+        lines.push(line);
+        lines.join("\n")
+    }
+
+    pub fn write_pgn<P: AsRef<Path>>(&self, path: P) -> io::Result<()> {
+        fs::write(path, self.to_pgn())
+    }
+}
+
+impl MoveNode {
+    pub fn new(san: String, chess_move: Move) -> MoveNode {
+        MoveNode {
+            san,
+            chess_move,
+            nags: vec![],
+            comments_before: vec![],
+            comments: vec![],
+            variations: vec![],
+        }
+    }
+}
+
+// Brace comments can't be escaped, so a "}" in the text is dropped rather
+// than letting it end the comment early
+fn comment_token(comment: &str) -> String {
+    format!("{{{}}}", comment.replace('}', ""))
+}
+
+// Black's moves only get a number ("12...") at the start of a line or after
+// a comment or variation interrupted the move pair
+fn push_line_tokens(line: &[MoveNode], first_move_number: u32, white_moves_first: bool, tokens: &mut Vec<String>) {
+    let mut move_number = first_move_number;
+    let mut white_to_move = white_moves_first;
+    let mut needs_number = true;
+
+    for node in line.iter() {
+        for comment in node.comments_before.iter() {
+            tokens.push(comment_token(comment));
+        }
+
+        // Move numbers stay on the same line as the move they belong to
+        if white_to_move {
+            tokens.push(format!("{}. {}", move_number, node.san));
+        } else if needs_number {
+            tokens.push(format!("{}... {}", move_number, node.san));
+        } else {
+            tokens.push(node.san.clone());
+        }
+
+        needs_number = false;
+
+        for nag in node.nags.iter() {
+            tokens.push(format!("${}", nag));
+        }
+
+        for comment in node.comments.iter() {
+            tokens.push(comment_token(comment));
+            needs_number = true;
+        }
+
+        for variation in node.variations.iter() {
+            let mut variation_tokens: Vec<String> = vec![];
+
+            push_line_tokens(variation, move_number, white_to_move, &mut variation_tokens);
+
+            if let Some(first) = variation_tokens.first_mut() {
+                first.insert(0, '(');
+            }
+
+            if let Some(last) = variation_tokens.last_mut() {
+                last.push(')');
+            }
+
+            tokens.extend(variation_tokens);
+            needs_number = true;
+        }
+
+        if !white_to_move {
+            move_number += 1;
+        }
+
+        white_to_move = !white_to_move;
+    }
+}
+
+// Where in the file a problem was found. Games are counted from 1, as are
+// lines and columns.
+#[derive(Clone)]
+#[derive(PartialEq)]
+#[derive(Debug)]
+pub struct PgnError {
+    pub game: usize,
+    pub line: usize,
+    pub column: usize,
+    pub kind: PgnErrorKind,
+}
+
+#[derive(Clone)]
+#[derive(PartialEq)]
+#[derive(Debug)]
+pub enum PgnErrorKind {
+    // Reading the file failed, with the message of the io::Error
+    Io(String),
+    UnexpectedCharacter(char),
+    UnterminatedString,
+    UnterminatedComment,
+    BadTag,
+    BadFen(FenError),
+    BadNag,
+    BadMove(SanError),
+    // A token that can't appear where it was found, such as a variation
+    // before any move or a closing parenthesis without an opening one
+    UnexpectedToken(String),
+    UnclosedVariation,
+}
+
+impl fmt::Display for PgnErrorKind {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        match *self {
+            PgnErrorKind::Io(ref message) => write!(f, "could not read the file: {}", message),
+            PgnErrorKind::UnexpectedCharacter(c) => write!(f, "unexpected character '{}'", c),
+            PgnErrorKind::UnterminatedString => write!(f, "string is never closed"),
+            PgnErrorKind::UnterminatedComment => write!(f, "comment is never closed"),
+            PgnErrorKind::BadTag => write!(f, "tag pairs look like [Name \"value\"]"),
+            PgnErrorKind::BadFen(ref error) => write!(f, "bad FEN tag: {}", error),
+            PgnErrorKind::BadNag => write!(f, "'$' has to be followed by a number"),
+            PgnErrorKind::BadMove(ref error) => write!(f, "{}", error),
+            PgnErrorKind::UnexpectedToken(ref token) => write!(f, "unexpected '{}'", token),
+            PgnErrorKind::UnclosedVariation => write!(f, "variation is never closed"),
+        }
+    }
+}
+
+impl fmt::Display for PgnError {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        write!(f, "game {}, line {}, column {}: {}", self.game, self.line, self.column, self.kind)
+    }
+}
+
+impl Error for PgnError {}
+
+#[derive(Clone)]
+#[derive(PartialEq)]
+#[derive(Debug)]
+enum TokenKind {
+    TagOpen,
+    TagClose,
+    Text(String),
+    // Move numbers are dropped, anything else that isn't punctuation is a symbol
+    Symbol(String),
+    Comment(String),
+    Nag(u32),
+    VariationOpen,
+    VariationClose,
+    Result(String),
+}
+
+struct Token {
+    kind: TokenKind,
+    line: usize,
+    column: usize,
+}
+
+// Annotation suffixes written onto a move, and the NAGs they stand for
+const SUFFIX_NAGS: [(&str, u32); 6] = [("!!", 3), ("??", 4), ("!?", 5), ("?!", 6), ("!", 1), ("?", 2)];
+
+const RESULT_TOKENS: [&str; 4] = ["1-0", "0-1", "1/2-1/2", "*"];
+
+// Splits one game's text into tokens, keeping the file position of each
+fn tokenize(lines: &[(usize, String)], game: usize) -> Result<Vec<Token>, PgnError> {
+    let mut tokens: Vec<Token> = vec![];
+    // Characters of every line, so comments and strings can run over line ends
+    let chars: Vec<(usize, usize, char)> = lines.iter()
+        .flat_map(|(line_number, line)| {
+            // Lines starting with % are escaped and skipped entirely
+            let skipped = line.starts_with('%');
+
+            line.chars()
+                .chain(std::iter::once('\n'))
+                .enumerate()
+                .filter(move |_| !skipped)
+                .map(move |(column, c)| (*line_number, column + 1, c))
+        })
+        .collect();
+
+    let error = |line: usize, column: usize, kind: PgnErrorKind| PgnError { game, line, column, kind };
+    let mut i = 0;
+
+    while i < chars.len() {
+        let (line, column, c) = chars[i];
+        let mut push = |kind: TokenKind| tokens.push(Token { kind, line, column });
+
+        i += 1;
+
+        match c {
+            _ if c.is_whitespace() => {}
+            '[' => push(TokenKind::TagOpen),
+            ']' => push(TokenKind::TagClose),
+            '(' => push(TokenKind::VariationOpen),
+            ')' => push(TokenKind::VariationClose),
+            '"' => {
+                let mut text = String::new();
+
+                loop {
+                    match chars.get(i) {
+                        Some(&(_, _, '"')) => break,
+                        Some(&(_, _, '\\')) if chars.get(i + 1).is_some_and(|&(_, _, next)| next == '"' || next == '\\') => {
+                            text.push(chars[i + 1].2);
+                            i += 1;
+                        }
+                        Some(&(_, _, inner)) if inner != '\n' => text.push(inner),
+                        _ => return Err(error(line, column, PgnErrorKind::UnterminatedString)),
+                    }
+
+                    i += 1;
+                }
+
+                i += 1;
+                push(TokenKind::Text(text));
+            }
+            '{' => {
+                let mut text = String::new();
+
+                loop {
+                    match chars.get(i) {
+                        Some(&(_, _, '}')) => break,
+                        Some(&(_, _, inner)) => text.push(if inner == '\n' { ' ' } else { inner }),
+                        None => return Err(error(line, column, PgnErrorKind::UnterminatedComment)),
+                    }
+
+                    i += 1;
+                }
+
+                i += 1;
+                push(TokenKind::Comment(text.trim().to_string()));
+            }
+            ';' => {
+                let mut text = String::new();
+
+                while let Some(&(_, _, inner)) = chars.get(i) {
+                    i += 1;
+
+                    if inner == '\n' {
+                        break;
+                    }
+
+                    text.push(inner);
+                }
+
+                push(TokenKind::Comment(text.trim().to_string()));
+            }
+            '$' => {
+                let mut digits = String::new();
+
+                while let Some(&(_, _, digit)) = chars.get(i) {
+                    if !digit.is_ascii_digit() {
+                        break;
+                    }
+
+                    digits.push(digit);
+                    i += 1;
+                }
+
+                match digits.parse() {
+                    Ok(nag) => push(TokenKind::Nag(nag)),
+                    Err(_) => return Err(error(line, column, PgnErrorKind::BadNag)),
+                }
+            }
+            _ if c.is_ascii_alphanumeric() || c == '*' => {
+                let mut symbol = c.to_string();
+
+                while let Some(&(_, _, next)) = chars.get(i) {
+                    if !(next.is_ascii_alphanumeric() || "_+#=:-/.!?".contains(next)) {
+                        break;
+                    }
+
+                    symbol.push(next);
+                    i += 1;
+                }
+
+                if RESULT_TOKENS.contains(&symbol.as_str()) {
+                    push(TokenKind::Result(symbol));
+                    continue;
+                }
+
+                // "12." and "12...", possibly run together with the move as in "12.Nf3"
+                let digits = symbol.chars().take_while(|c| c.is_ascii_digit()).count();
+                let dots = symbol[digits..].chars().take_while(|&c| c == '.').count();
+
+                if digits > 0 && dots > 0 {
+                    symbol = symbol[digits + dots..].to_string();
+                } else if digits > 0 && digits == symbol.len() {
+                    symbol.clear();
+                }
+
+                if !symbol.is_empty() {
+                    push(TokenKind::Symbol(symbol));
+                }
+            }
+            _ => return Err(error(line, column, PgnErrorKind::UnexpectedCharacter(c))),
+        }
+    }
+
+    Ok(tokens)
+}
+
+// Builds one Game from its tokens, checking every move against the position
+struct GameParser {
+    tokens: Vec<Token>,
+    next: usize,
+    game: usize,
+    // Token the FEN tag starts at, to point at when it doesn't parse
+    fen_tag: Option<usize>,
+}
+
+impl GameParser {
+    fn error(&self, token: &Token, kind: PgnErrorKind) -> PgnError {
+        PgnError { game: self.game, line: token.line, column: token.column, kind }
+    }
+
+    fn parse(&mut self) -> Result<Game, PgnError> {
+        let tags = self.parse_tags()?;
+
+        let fen_string = tags.iter()
+            .find(|(name, _)| name == "FEN")
+            .map(|(_, fen)| fen.clone())
+            .unwrap_or(START_POSITION.to_string());
+
+        let start = match (Fen { fen_string }).parse() {
+            Ok(start) => start,
+            Err(error) => return Err(self.error(&self.tokens[self.fen_tag.unwrap_or(0)], PgnErrorKind::BadFen(error))),
+        };
+
+        let mut game = Game {
+            tags,
+            moves: vec![],
+            first_move_number: start.fullmove_number,
+            white_moves_first: start.white_to_move,
+        };
+
+        let (moves, result) = self.parse_line(start, None)?;
+
+        game.moves = moves;
+
+        if let Some(result) = result {
+            if game.get_tag("Result").is_none() {
+                game.set_tag("Result", &result);
+            }
+        }
+
+        Ok(game)
+    }
+
+    fn parse_tags(&mut self) -> Result<Vec<(String, String)>, PgnError> {
+        let mut tags: Vec<(String, String)> = vec![];
+
+        while self.tokens.get(self.next).is_some_and(|token| token.kind == TokenKind::TagOpen) {
+            let tag = &self.tokens[self.next..];
+
+            match (tag.get(1).map(|token| &token.kind), tag.get(2).map(|token| &token.kind), tag.get(3).map(|token| &token.kind)) {
+                (Some(TokenKind::Symbol(name)), Some(TokenKind::Text(value)), Some(TokenKind::TagClose)) => {
+                    if name == "FEN" {
+                        self.fen_tag = Some(self.next);
+                    }
+
+                    tags.push((name.clone(), value.clone()));
+                    self.next += 4;
+                }
+                _ => return Err(self.error(&tag[0], PgnErrorKind::BadTag)),
+            }
+        }
+
+        Ok(tags)
+    }
+
+    // Reads moves until the line ends: at the result for the main line, at
+    // the closing parenthesis for a variation. `opening` is the parenthesis
+    // a variation started at.
+    fn parse_line(&mut self, start: Position, opening: Option<usize>) -> Result<(Vec<MoveNode>, Option<String>), PgnError> {
+        let mut line: Vec<MoveNode> = vec![];
+        let mut position = start;
+        // The position before the last move, which its variations start from.
+        // Only a scratch copy, so keeping it doesn't copy the whole game each ply.
+        let mut previous: Option<Position> = None;
+        let mut comments_before: Vec<String> = vec![];
+
+        while self.next < self.tokens.len() {
+            let index = self.next;
+            self.next += 1;
+
+            match self.tokens[index].kind.clone() {
+                TokenKind::Symbol(symbol) => {
+                    let (san, nags) = split_suffix(&symbol);
+
+                    let chess_move = position.parse_san(san)
+                        .map_err(|error| self.error(&self.tokens[index], PgnErrorKind::BadMove(error)))?;
+
+                    let mut node = MoveNode::new(position.to_san(chess_move), chess_move);
+
+                    node.nags = nags;
+                    node.comments_before = std::mem::take(&mut comments_before);
+
+                    previous = Some(position.get_scratch_copy());
+                    position.make_move(chess_move);
+                    line.push(node);
+                }
+                TokenKind::Nag(nag) => match line.last_mut() {
+                    Some(node) => node.nags.push(nag),
+                    None => return Err(self.error(&self.tokens[index], PgnErrorKind::UnexpectedToken(format!("${}", nag)))),
+                },
+                TokenKind::Comment(comment) => match line.last_mut() {
+                    Some(node) => node.comments.push(comment),
+                    None => comments_before.push(comment),
+                },
+                TokenKind::VariationOpen => {
+                    let variation_start = match previous {
+                        Some(ref previous) => previous.get_scratch_copy(),
+                        None => return Err(self.error(&self.tokens[index], PgnErrorKind::UnexpectedToken("(".to_string()))),
+                    };
+
+                    let (variation, _) = self.parse_line(variation_start, Some(index))?;
+
+                    if let Some(node) = line.last_mut() {
+                        node.variations.push(variation);
+                    }
+                }
+                TokenKind::VariationClose => {
+                    if opening.is_none() {
+                        return Err(self.error(&self.tokens[index], PgnErrorKind::UnexpectedToken(")".to_string())));
+                    }
+
+                    return Ok((line, None));
+                }
+                TokenKind::Result(result) => {
+                    if let Some(opening) = opening {
+                        return Err(self.error(&self.tokens[opening], PgnErrorKind::UnclosedVariation));
+                    }
+
+                    return Ok((line, Some(result)));
+                }
+                TokenKind::TagOpen | TokenKind::TagClose | TokenKind::Text(_) => {
+                    return Err(self.error(&self.tokens[index], PgnErrorKind::UnexpectedToken("tag".to_string())));
+                }
+            }
+        }
+
+        if let Some(opening) = opening {
+            return Err(self.error(&self.tokens[opening], PgnErrorKind::UnclosedVariation));
+        }
+
+        Ok((line, None))
+    }
+}
+
+// Separates "!", "?!" and the like from the end of a move, as NAGs
+fn split_suffix(symbol: &str) -> (&str, Vec<u32>) {
+    for &(suffix, nag) in SUFFIX_NAGS.iter() {
+        if let Some(san) = symbol.strip_suffix(suffix) {
+            return (san, vec![nag]);
+        }
+    }
+
+    (symbol, vec![])
+}
+
+// Reads games from PGN text one at a time, so a large database never has to
+// be held in memory. Iterating yields each game or the error that stopped it;
+// the reader then carries on with the next game.
+pub struct PgnReader<R: BufRead> {
+    lines: io::Lines<R>,
+    line_number: usize,
+    // The first tag line of the next game, read while looking for the end of the last one
+    pending: Option<(usize, String)>,
+    game_number: usize,
+    // Tokens after the result of the last game: games without tags that
+    // follow it in the same block of lines
+    leftover: Vec<Token>,
+    finished: bool,
+}
+
+impl PgnReader<BufReader<File>> {
+    pub fn open<P: AsRef<Path>>(path: P) -> io::Result<PgnReader<BufReader<File>>> {
+        Ok(PgnReader::new(BufReader::new(File::open(path)?)))
+    }
+}
+
+impl<R: BufRead> PgnReader<R> {
+    pub fn new(reader: R) -> PgnReader<R> {
+        PgnReader {
+            lines: reader.lines(),
+            line_number: 0,
+            pending: None,
+            game_number: 0,
+            leftover: vec![],
+            finished: false,
+        }
+    }
+
+    // The lines of the next game. A game ends where a tag line follows its
+    // movetext, outside of any comment.
+    fn read_game_lines(&mut self) -> Result<Vec<(usize, String)>, PgnError> {
+        let mut lines: Vec<(usize, String)> = self.pending.take().into_iter().collect();
+        let mut in_movetext = false;
+        let mut in_comment = false;
+
+        loop {
+            let line = match self.lines.next() {
+                Some(Ok(line)) => line,
+                Some(Err(error)) => {
+                    self.finished = true;
+
+                    return Err(PgnError {
+                        game: self.game_number + 1,
+                        line: self.line_number + 1,
+                        column: 1,
+                        kind: PgnErrorKind::Io(error.to_string()),
+                    });
+                }
+                None => {
+                    self.finished = true;
+                    break;
+                }
+            };
+
+            self.line_number += 1;
+
+            let trimmed = line.trim_start();
+
+            if !in_comment && trimmed.starts_with('[') {
+                if in_movetext {
+                    self.pending = Some((self.line_number, line));
+                    break;
+                }
+            } else if !trimmed.is_empty() && !trimmed.starts_with('%') {
+                in_movetext = true;
+
+                for c in trimmed.chars() {
+                    match c {
+                        '{' if !in_comment => in_comment = true,
+                        '}' if in_comment => in_comment = false,
+                        ';' if !in_comment => break,
+                        _ => {}
+                    }
+                }
+            }
+
+            lines.push((self.line_number, line));
+        }
+
+        Ok(lines)
+    }
+}
+
+impl<R: BufRead> Iterator for PgnReader<R> {
+    type Item = Result<Game, PgnError>;
+
+    fn next(&mut self) -> Option<Result<Game, PgnError>> {
+        let tokens = if !self.leftover.is_empty() {
+            self.game_number += 1;
+            std::mem::take(&mut self.leftover)
+        } else {
+            if self.finished && self.pending.is_none() {
+                return None;
+            }
+
+            let lines = match self.read_game_lines() {
+                Ok(lines) => lines,
+                Err(error) => return Some(Err(error)),
+            };
+
+            // Nothing but blank lines left
+            if lines.iter().all(|(_, line)| line.trim().is_empty()) {
+                return None;
+            }
+
+            self.game_number += 1;
+
+            match tokenize(&lines, self.game_number) {
+                Ok(tokens) => tokens,
+                Err(error) => return Some(Err(error)),
+            }
+        };
+
+        let mut parser = GameParser { tokens, next: 0, game: self.game_number, fen_tag: None };
+        let game = parser.parse();
+
+        // After an error the next game starts behind the next result
+        let rest = match game {
+            Ok(_) => parser.next,
+            Err(_) => parser.tokens.iter()
+                .skip(parser.next.saturating_sub(1))
+                .position(|token| matches!(token.kind, TokenKind::Result(_)))
+                .map_or(parser.tokens.len(), |offset| parser.next.saturating_sub(1) + offset + 1),
+        };
+
+        // Comments trailing the result don't make a game of their own
+        let rest = parser.tokens.split_off(rest.min(parser.tokens.len()));
+
+        if rest.iter().any(|token| !matches!(token.kind, TokenKind::Comment(_))) {
+            self.leftover = rest;
+        }
+
+        Some(game)
+    }
+}
@@ -0,0 +1,801 @@
+// Game state and rules without any graphics. Squares use the same board_index
+// layout as the GUI: 0 is the top left corner (a8), 63 the bottom right (h1),
+// and rows grow downwards, so white pawns move towards row 0.
+//
+// Pieces are kept in bitboards (see bitboard.rs); Piece values are only built
+// when a caller asks what stands on a square.
+
+use std::collections::hash_map::DefaultHasher;
+use std::hash::{Hash, Hasher};
+use bitboard::*;
+use square::Square;
+
+#[derive(Clone)]
+#[derive(PartialEq)]
+#[derive(Debug)]
+pub struct Piece {
+    pub worth: i32,
+    pub name: String,
+    pub capturable: bool,
+    pub white: bool
+}
+
+// Kingside is towards the h-file (column 7), queenside towards the a-file (column 0)
+#[derive(Clone)]
+#[derive(Copy)]
+#[derive(PartialEq)]
+#[derive(Debug)]
+pub struct CastlingRights {
+    pub white_kingside: bool,
+    pub white_queenside: bool,
+    pub black_kingside: bool,
+    pub black_queenside: bool,
+}
+
+// A move packed into 16 bits: the origin square in bits 0-5, the target square
+// in bits 6-11 and the kind a pawn promotes to in bits 12-14. Castling and en
+// passant aren't flagged, the position works them out from the pieces.
+#[derive(Clone)]
+#[derive(Copy)]
+#[derive(PartialEq)]
+#[derive(Eq)]
+#[derive(Hash)]
+#[derive(Debug)]
+pub struct Move(u16);
+
+// Everything make_move can't work out again from the move itself
+#[derive(Clone)]
+#[derive(Debug)]
+struct Undo {
+    chess_move: Move,
+    // Kind and square of the captured piece, which is not the target square for en passant
+    captured: Option<(usize, u32)>,
+    castling_rights: CastlingRights,
+    en_passant: Option<u32>,
+    halfmove_clock: u32,
+}
+
+#[derive(Clone)]
+#[derive(PartialEq)]
+#[derive(Debug)]
+pub enum GameResult {
+    WhiteWins,
+    BlackWins,
+    Draw(DrawReason),
+}
+
+#[derive(Clone)]
+#[derive(PartialEq)]
+#[derive(Debug)]
+pub enum DrawReason {
+    Stalemate,
+    InsufficientMaterial,
+    FiftyMoveRule,
+    SeventyFiveMoveRule,
+    ThreefoldRepetition,
+    FivefoldRepetition,
+}
+
+#[derive(Clone)]
+#[derive(Debug)]
+pub struct Position {
+    // One bitboard per colour and piece kind, indexed [colour][kind] with white = 0
+    pieces: [[u64; 6]; 2],
+    // Every square holding a piece of each colour
+    occupancy: [u64; 2],
+    pub white_to_move: bool,
+    pub castling_rights: CastlingRights,
+    // Square skipped by a pawn's double push on the previous move
+    pub en_passant: Option<u32>,
+    // Plies since the last capture or pawn move, for the fifty-move rule
+    pub halfmove_clock: u32,
+    pub fullmove_number: u32,
+    // Hash of every position reached so far, for repetition detection
+    pub position_history: Vec<u64>,
+    // One record per move played with make_move, newest last
+    undo_stack: Vec<Undo>,
+}
+
+pub const PROMOTION_CHOICES: [&str; 4] = ["Queen", "Rook", "Bishop", "Knight"];
+const PROMOTION_KINDS: [usize; 4] = [QUEEN, ROOK, BISHOP, KNIGHT];
+
+fn colour_index(white: bool) -> usize {
+    if white { 0 } else { 1 }
+}
+
+impl Piece {
+    pub fn new(name: &str, white: bool) -> Piece {
+        let worth = match name {
+            "Pawn" => 1,
+            "Knight" | "Bishop" => 3,
+            "Rook" => 5,
+            "Queen" => 9,
+            _ => 0,
+        };
+
+        Piece {
+            worth,
+            name: name.to_string(),
+            capturable: name != "King",
+            white
+        }
+    }
+}
+
+impl Move {
+    pub fn new(from: u32, to: u32) -> Move {
+        Move((from | (to << 6)) as u16)
+    }
+
+    pub fn with_promotion(from: u32, to: u32, kind: usize) -> Move {
+        Move((from | (to << 6) | ((kind as u32) << 12)) as u16)
+    }
+
+    pub fn get_from(&self) -> u32 {
+        (self.0 & 0x3f) as u32
+    }
+
+    pub fn get_to(&self) -> u32 {
+        ((self.0 >> 6) & 0x3f) as u32
+    }
+
+    // Kind the pawn turns into, None for anything but a promotion
+    pub fn get_promotion(&self) -> Option<usize> {
+        match (self.0 >> 12) as usize {
+            PAWN => None,
+            kind => Some(kind),
+        }
+    }
+}
+
+impl CastlingRights {
+    pub fn allows(&self, white: bool, kingside: bool) -> bool {
+        match (white, kingside) {
+            (true, true) => self.white_kingside,
+            (true, false) => self.white_queenside,
+            (false, true) => self.black_kingside,
+            (false, false) => self.black_queenside,
+        }
+    }
+}
+
+impl GameResult {
+    pub fn headline(&self) -> &str {
+        match *self {
+            GameResult::WhiteWins => "White wins",
+            GameResult::BlackWins => "Black wins",
+            GameResult::Draw(_) => "Draw",
+        }
+    }
+
+    pub fn reason(&self) -> &str {
+        match *self {
+            GameResult::WhiteWins | GameResult::BlackWins => "Checkmate",
+            GameResult::Draw(DrawReason::Stalemate) => "Stalemate",
+            GameResult::Draw(DrawReason::InsufficientMaterial) => "Insufficient material",
+            GameResult::Draw(DrawReason::FiftyMoveRule) => "Fifty-move rule",
+            GameResult::Draw(DrawReason::SeventyFiveMoveRule) => "75-move rule",
+            GameResult::Draw(DrawReason::ThreefoldRepetition) => "Threefold repetition",
+            GameResult::Draw(DrawReason::FivefoldRepetition) => "Fivefold repetition",
+        }
+    }
+}
+
+impl Position {
+    // No pieces, white to move and no castling rights
+    pub fn empty() -> Position {
+        Position {
+            pieces: [[0; 6]; 2],
+            occupancy: [0; 2],
+            white_to_move: true,
+            castling_rights: CastlingRights {
+                white_kingside: false,
+                white_queenside: false,
+                black_kingside: false,
+                black_queenside: false,
+            },
+            en_passant: None,
+            halfmove_clock: 0,
+            fullmove_number: 1,
+            position_history: vec![],
+            undo_stack: vec![],
+        }
+    }
+
+    // Colour and kind of whatever stands on the square, without building a Piece
+    fn kind_at(&self, index: u32) -> Option<(bool, usize)> {
+        let bit = square_bit(index);
+
+        for (colour, boards) in self.pieces.iter().enumerate() {
+            if self.occupancy[colour] & bit == 0 {
+                continue;
+            }
+
+            if let Some(kind) = boards.iter().position(|board| board & bit != 0) {
+                return Some((colour == 0, kind));
+            }
+        }
+
+        None
+    }
+
+    pub fn piece_at(&self, index: u32) -> Option<Piece> {
+        if index >= 64 {
+            return None;
+        }
+
+        self.kind_at(index).map(|(white, kind)| Piece::new(PIECE_NAMES[kind], white))
+    }
+
+    pub fn put_piece(&mut self, index: u32, piece: &Piece) {
+        if let Some(kind) = kind_from_name(&piece.name) {
+            self.remove_piece(index);
+            self.add_kind(index, piece.white, kind);
+        }
+    }
+
+    pub fn remove_piece(&mut self, index: u32) {
+        if let Some((white, kind)) = self.kind_at(index) {
+            self.toggle_kind(index, white, kind);
+        }
+    }
+
+    fn add_kind(&mut self, index: u32, white: bool, kind: usize) {
+        let bit = square_bit(index);
+        let colour = colour_index(white);
+
+        self.pieces[colour][kind] |= bit;
+        self.occupancy[colour] |= bit;
+    }
+
+    fn toggle_kind(&mut self, index: u32, white: bool, kind: usize) {
+        let bit = square_bit(index);
+        let colour = colour_index(white);
+
+        self.pieces[colour][kind] ^= bit;
+        self.occupancy[colour] ^= bit;
+    }
+
+    pub fn get_piece_bitboard(&self, white: bool, kind: usize) -> u64 {
+        self.pieces[colour_index(white)][kind]
+    }
+
+    pub fn get_occupancy(&self, white: bool) -> u64 {
+        self.occupancy[colour_index(white)]
+    }
+
+    pub fn get_all_occupancy(&self) -> u64 {
+        self.occupancy[0] | self.occupancy[1]
+    }
+
+    fn is_empty(&self, index: u32) -> bool {
+        self.get_all_occupancy() & square_bit(index) == 0
+    }
+
+    fn push_moves(moves: &mut Vec<Move>, from: u32, mut targets: u64) {
+        while let Some(to) = pop_square(&mut targets) {
+            moves.push(Move::new(from, to));
+        }
+    }
+
+    fn get_pawn_moves(&self, from: u32, white: bool, moves: &mut Vec<Move>) {
+        // White pawns walk up the board (towards rank 8, row 0), black pawns walk down
+        let direction: i32 = if white { 1 } else { -1 };
+        let start_row = if white { 6 } else { 1 };
+        let last_row = if white { ROW_0 } else { ROW_7 };
+        let mut targets: u64 = 0;
+
+        let first = Square::from_index(from).and_then(|square| square.offset(0, direction));
+
+        if let Some(first) = first {
+            if self.is_empty(first.index()) {
+                targets |= square_bit(first.index());
+
+                // Two squares only from the starting rank, and only over an empty square
+                if from / 8 == start_row {
+                    if let Some(second) = first.offset(0, direction) {
+                        if self.is_empty(second.index()) {
+                            targets |= square_bit(second.index());
+                        }
+                    }
+                }
+            }
+        }
+
+        // Diagonal captures, including en passant onto the square a pawn just skipped
+        let attacks = pawn_attacks(from, white);
+        targets |= attacks & self.get_occupancy(!white);
+
+        if let Some(target) = self.en_passant {
+            if attacks & square_bit(target) != 0 && self.is_en_passant_capture(from, target, white) {
+                targets |= square_bit(target);
+            }
+        }
+
+        let mut promotions = targets & last_row;
+        Self::push_moves(moves, from, targets & !last_row);
+
+        while let Some(to) = pop_square(&mut promotions) {
+            for &kind in PROMOTION_KINDS.iter() {
+                moves.push(Move::with_promotion(from, to, kind));
+            }
+        }
+    }
+
+    // The en passant square only counts if the pawn that skipped over it sits
+    // next to the capturing pawn, on the capturing pawn's row.
+    fn is_en_passant_capture(&self, from: u32, to: u32, white: bool) -> bool {
+        if self.en_passant != Some(to) {
+            return false;
+        }
+
+        let passed_index = from - (from % 8) + (to % 8);
+
+        self.get_piece_bitboard(!white, PAWN) & square_bit(passed_index) != 0
+    }
+
+    fn get_castling_moves(&self, king_index: u32, white: bool, moves: &mut Vec<Move>) {
+        // Only a king still on its e1 or e8 starting square can castle
+        if king_index != if white { 60 } else { 4 } {
+            return;
+        }
+
+        let row_start = (king_index / 8) * 8;
+
+        for &(kingside, rook_column) in [(true, 7), (false, 0)].iter() {
+            if !self.castling_rights.allows(white, kingside) {
+                continue;
+            }
+
+            let rook_index = row_start + rook_column;
+
+            if self.get_piece_bitboard(white, ROOK) & square_bit(rook_index) == 0 {
+                continue;
+            }
+
+            // Every square between the king and the rook has to be empty
+            let (low, high) = if kingside { (king_index + 1, rook_index) } else { (rook_index + 1, king_index) };
+
+            if !(low..high).all(|index| self.is_empty(index)) {
+                continue;
+            }
+
+            // The king may not castle out of, through or into check
+            let target_index = if kingside { king_index + 2 } else { king_index - 2 };
+            let crossed_index = (king_index + target_index) / 2;
+
+            if [king_index, crossed_index, target_index].iter().any(|&index| self.is_square_attacked(index, !white)) {
+                continue;
+            }
+
+            moves.push(Move::new(king_index, target_index));
+        }
+    }
+
+    // Every move the piece on `from` could make, ignoring whether it leaves its own king in check
+    pub fn get_pseudo_legal_moves_from(&self, from: u32) -> Vec<Move> {
+        let mut moves: Vec<Move> = vec![];
+
+        let (white, kind) = match self.kind_at(from) {
+            Some(found) => found,
+            None => return moves,
+        };
+
+        let not_own = !self.get_occupancy(white);
+        let occupancy = self.get_all_occupancy();
+
+        match kind {
+            PAWN => self.get_pawn_moves(from, white, &mut moves),
+            KNIGHT => Self::push_moves(&mut moves, from, knight_attacks(from) & not_own),
+            BISHOP => Self::push_moves(&mut moves, from, bishop_attacks(from, occupancy) & not_own),
+            ROOK => Self::push_moves(&mut moves, from, rook_attacks(from, occupancy) & not_own),
+            QUEEN => Self::push_moves(&mut moves, from, queen_attacks(from, occupancy) & not_own),
+            _ => {
+                Self::push_moves(&mut moves, from, king_attacks(from) & not_own);
+                self.get_castling_moves(from, white, &mut moves);
+            }
+        }
+
+        moves
+    }
+
+    // get_pseudo_legal_moves_from without the moves that would leave our own king in check
+    pub fn get_legal_moves_from(&self, from: u32) -> Vec<Move> {
+        let white = match self.kind_at(from) {
+            Some((white, _)) => white,
+            None => return vec![],
+        };
+
+        self.get_pseudo_legal_moves_from(from)
+            .into_iter()
+            .filter(|&chess_move| !self.leaves_king_in_check(chess_move, white))
+            .collect()
+    }
+
+    // All legal moves for the side to move
+    pub fn get_legal_moves(&self) -> Vec<Move> {
+        let mut moves: Vec<Move> = vec![];
+        let mut own = self.get_occupancy(self.white_to_move);
+
+        while let Some(from) = pop_square(&mut own) {
+            moves.extend(self.get_legal_moves_from(from));
+        }
+
+        moves
+    }
+
+    fn has_legal_moves(&self) -> bool {
+        let mut own = self.get_occupancy(self.white_to_move);
+
+        while let Some(from) = pop_square(&mut own) {
+            if !self.get_legal_moves_from(from).is_empty() {
+                return true;
+            }
+        }
+
+        false
+    }
+
+    // A copy without the position history and undo stack, for trying moves
+    // out. Copying those would make every copy cost as much as the game so far.
+    // Repetitions aren't counted on the copy and its moves can only be taken
+    // back as far as the copy.
+    pub fn get_scratch_copy(&self) -> Position {
+        Position {
+            position_history: vec![],
+            undo_stack: vec![],
+            ..*self
+        }
+    }
+
+    // Plays the move on a scratch copy and checks the mover's king
+    fn leaves_king_in_check(&self, chess_move: Move, white: bool) -> bool {
+        let mut scratch = self.get_scratch_copy();
+
+        scratch.move_pieces(chess_move);
+        scratch.is_king_in_check(white)
+    }
+
+    // Checks whether any piece of the `by_white` colour attacks `square`, by
+    // looking outwards from the square with each piece's attack pattern.
+    pub fn is_square_attacked(&self, square: u32, by_white: bool) -> bool {
+        let attacker = |kind: usize| self.get_piece_bitboard(by_white, kind);
+        let occupancy = self.get_all_occupancy();
+
+        // A pawn attacks this square exactly when a pawn of the other colour
+        // standing here would attack the pawn's square
+        knight_attacks(square) & attacker(KNIGHT) != 0
+            || pawn_attacks(square, !by_white) & attacker(PAWN) != 0
+            || king_attacks(square) & attacker(KING) != 0
+            || rook_attacks(square, occupancy) & (attacker(ROOK) | attacker(QUEEN)) != 0
+            || bishop_attacks(square, occupancy) & (attacker(BISHOP) | attacker(QUEEN)) != 0
+    }
+
+    pub fn get_king_index(&self, white: bool) -> Option<u32> {
+        let kings = self.get_piece_bitboard(white, KING);
+
+        if kings == 0 {
+            None
+        } else {
+            Some(kings.trailing_zeros())
+        }
+    }
+
+    pub fn is_king_in_check(&self, white: bool) -> bool {
+        match self.get_king_index(white) {
+            Some(king_index) => self.is_square_attacked(king_index, !white),
+            None => false,
+        }
+    }
+
+    // Moves the pieces for a move, including the castling rook, the pawn taken
+    // en passant and the promoted piece. No other state is touched.
+    fn move_pieces(&mut self, chess_move: Move) {
+        let from = chess_move.get_from();
+        let to = chess_move.get_to();
+
+        let (white, kind) = match self.kind_at(from) {
+            Some(found) => found,
+            None => return,
+        };
+
+        let from_column = from % 8;
+        let to_column = to % 8;
+
+        // A pawn moving diagonally onto an empty square took en passant
+        if kind == PAWN && from_column != to_column && self.is_empty(to) {
+            self.remove_piece(from - from_column + to_column);
+        }
+
+        // A castling king moves two columns, the rook jumps over to the square it crossed
+        if kind == KING && from_column.abs_diff(to_column) == 2 {
+            let row_start = to - to_column;
+            let (rook_from, rook_to) = if to_column > from_column { (row_start + 7, to - 1) } else { (row_start, to + 1) };
+
+            self.toggle_kind(rook_from, white, ROOK);
+            self.toggle_kind(rook_to, white, ROOK);
+        }
+
+        let landing_kind = chess_move.get_promotion().unwrap_or(kind);
+
+        self.remove_piece(to);
+        self.toggle_kind(from, white, kind);
+        self.add_kind(to, white, landing_kind);
+    }
+
+    // Called after a piece moves from `from` to `to`. Moving the king gives up
+    // both rights, and anything leaving or landing on a corner means that rook
+    // has either moved or been captured.
+    fn update_castling_rights(&mut self, moved_kind: usize, white: bool, from: u32, to: u32) {
+        if moved_kind == KING {
+            if white {
+                self.castling_rights.white_kingside = false;
+                self.castling_rights.white_queenside = false;
+            } else {
+                self.castling_rights.black_kingside = false;
+                self.castling_rights.black_queenside = false;
+            }
+        }
+
+        for &index in [from, to].iter() {
+            match index {
+                0 => self.castling_rights.black_queenside = false,
+                7 => self.castling_rights.black_kingside = false,
+                56 => self.castling_rights.white_queenside = false,
+                63 => self.castling_rights.white_kingside = false,
+                _ => {}
+            }
+        }
+    }
+
+    // Plays a move and updates the rest of the game state. The move is expected
+    // to come from get_legal_moves; nothing is validated here.
+    pub fn make_move(&mut self, chess_move: Move) {
+        let from = chess_move.get_from();
+        let to = chess_move.get_to();
+
+        let (white, kind) = match self.kind_at(from) {
+            Some(found) => found,
+            None => return,
+        };
+
+        let is_pawn = kind == PAWN;
+        let mut captured = self.kind_at(to).map(|(_, captured_kind)| (captured_kind, to));
+
+        // A pawn moving diagonally onto an empty square takes en passant
+        if is_pawn && captured.is_none() && from % 8 != to % 8 {
+            captured = Some((PAWN, from - (from % 8) + (to % 8)));
+        }
+
+        self.undo_stack.push(Undo {
+            chess_move,
+            captured,
+            castling_rights: self.castling_rights,
+            en_passant: self.en_passant,
+            halfmove_clock: self.halfmove_clock,
+        });
+
+        self.move_pieces(chess_move);
+        self.update_castling_rights(kind, white, from, to);
+
+        // En passant is only available for one ply after a double push
+        self.en_passant = if is_pawn && from.abs_diff(to) == 16 {
+            Some((from + to) / 2)
+        } else {
+            None
+        };
+
+        // Captures and pawn moves reset the fifty-move count
+        if captured.is_some() || is_pawn {
+            self.halfmove_clock = 0;
+        } else {
+            self.halfmove_clock += 1;
+        }
+
+        if !self.white_to_move {
+            self.fullmove_number += 1;
+        }
+
+        self.white_to_move = !self.white_to_move;
+
+        let position_hash = self.get_position_hash();
+        self.position_history.push(position_hash);
+    }
+
+    // Takes back the last move played with make_move and returns it, or None
+    // when there is nothing to take back.
+    pub fn unmake_move(&mut self) -> Option<Move> {
+        let undo = self.undo_stack.pop()?;
+        let from = undo.chess_move.get_from();
+        let to = undo.chess_move.get_to();
+
+        self.white_to_move = !self.white_to_move;
+
+        if !self.white_to_move {
+            self.fullmove_number -= 1;
+        }
+
+        let white = self.white_to_move;
+
+        let landed_kind = match self.kind_at(to) {
+            Some((_, landed_kind)) => landed_kind,
+            None => return None,
+        };
+
+        let moved_kind = if undo.chess_move.get_promotion().is_some() { PAWN } else { landed_kind };
+
+        self.toggle_kind(to, white, landed_kind);
+        self.add_kind(from, white, moved_kind);
+
+        if let Some((captured_kind, captured_index)) = undo.captured {
+            self.add_kind(captured_index, !white, captured_kind);
+        }
+
+        // Put a castling rook back in its corner
+        if moved_kind == KING && (from % 8).abs_diff(to % 8) == 2 {
+            let row_start = to - (to % 8);
+            let (rook_from, rook_to) = if to > from { (row_start + 7, to - 1) } else { (row_start, to + 1) };
+
+            self.toggle_kind(rook_to, white, ROOK);
+            self.toggle_kind(rook_from, white, ROOK);
+        }
+
+        self.castling_rights = undo.castling_rights;
+        self.en_passant = undo.en_passant;
+        self.halfmove_clock = undo.halfmove_clock;
+        self.position_history.pop();
+
+        Some(undo.chess_move)
+    }
+
+    // Counts the leaf nodes of the legal move tree `depth` plies deep. The
+    // last ply is counted straight from the move list instead of being played.
+    pub fn perft(&mut self, depth: u32) -> u64 {
+        if depth == 0 {
+            return 1;
+        }
+
+        let moves = self.get_legal_moves();
+
+        if depth == 1 {
+            return moves.len() as u64;
+        }
+
+        let mut nodes = 0;
+
+        for chess_move in moves {
+            self.make_move(chess_move);
+            nodes += self.perft(depth - 1);
+            self.unmake_move();
+        }
+
+        nodes
+    }
+
+    // perft split by the first move, for tracking down which move a wrong
+    // count comes from
+    pub fn divide(&mut self, depth: u32) -> Vec<(Move, u64)> {
+        let mut counts: Vec<(Move, u64)> = vec![];
+
+        if depth == 0 {
+            return counts;
+        }
+
+        for chess_move in self.get_legal_moves() {
+            self.make_move(chess_move);
+            counts.push((chess_move, self.perft(depth - 1)));
+            self.unmake_move();
+        }
+
+        counts
+    }
+
+    // Evaluated for the side to move. No legal moves means checkmate if that
+    // side is in check and stalemate otherwise, which both take precedence over
+    // the automatic draw rules.
+    pub fn get_game_result(&self) -> Option<GameResult> {
+        if self.has_legal_moves() {
+            return self.get_draw_reason().map(GameResult::Draw);
+        }
+
+        if !self.is_king_in_check(self.white_to_move) {
+            return Some(GameResult::Draw(DrawReason::Stalemate));
+        }
+
+        if self.white_to_move {
+            Some(GameResult::BlackWins)
+        } else {
+            Some(GameResult::WhiteWins)
+        }
+    }
+
+    // The automatic draws, checked from the strongest rule down
+    pub fn get_draw_reason(&self) -> Option<DrawReason> {
+        if self.is_insufficient_material() {
+            Some(DrawReason::InsufficientMaterial)
+        } else if self.is_seventy_five_move_draw() {
+            Some(DrawReason::SeventyFiveMoveRule)
+        } else if self.is_fivefold_repetition() {
+            Some(DrawReason::FivefoldRepetition)
+        } else if self.is_fifty_move_draw() {
+            Some(DrawReason::FiftyMoveRule)
+        } else if self.is_threefold_repetition() {
+            Some(DrawReason::ThreefoldRepetition)
+        } else {
+            None
+        }
+    }
+
+    pub fn is_fifty_move_draw(&self) -> bool {
+        self.halfmove_clock >= 100
+    }
+
+    pub fn is_seventy_five_move_draw(&self) -> bool {
+        self.halfmove_clock >= 150
+    }
+
+    // How many times the current position has been reached, itself included.
+    // The history starts with the position the game was set up from.
+    pub fn get_repetition_count(&self) -> usize {
+        let current = self.get_position_hash();
+
+        self.position_history.iter().filter(|&&hash| hash == current).count()
+    }
+
+    pub fn is_threefold_repetition(&self) -> bool {
+        self.get_repetition_count() >= 3
+    }
+
+    pub fn is_fivefold_repetition(&self) -> bool {
+        self.get_repetition_count() >= 5
+    }
+
+    // K v K, K + minor v K, or only bishops left and all of them on one square colour
+    pub fn is_insufficient_material(&self) -> bool {
+        let heavy_or_pawns = [PAWN, ROOK, QUEEN].iter()
+            .any(|&kind| self.get_piece_bitboard(true, kind) | self.get_piece_bitboard(false, kind) != 0);
+
+        if heavy_or_pawns {
+            return false;
+        }
+
+        let knights = self.get_piece_bitboard(true, KNIGHT) | self.get_piece_bitboard(false, KNIGHT);
+        let bishops = self.get_piece_bitboard(true, BISHOP) | self.get_piece_bitboard(false, BISHOP);
+
+        if (knights | bishops).count_ones() <= 1 {
+            return true;
+        }
+
+        // Light squares are the ones where row + column is even
+        let light_squares: u64 = 0xaa55_aa55_aa55_aa55;
+
+        knights == 0 && (bishops & light_squares == 0 || bishops & !light_squares == 0)
+    }
+
+    // Two positions are the same when the pieces, side to move, castling rights and
+    // en passant options all match. En passant only counts when a pawn can actually take.
+    pub fn get_position_hash(&self) -> u64 {
+        let mut hasher = DefaultHasher::new();
+
+        self.pieces.hash(&mut hasher);
+        self.white_to_move.hash(&mut hasher);
+
+        for &right in [
+            self.castling_rights.white_kingside,
+            self.castling_rights.white_queenside,
+            self.castling_rights.black_kingside,
+            self.castling_rights.black_queenside,
+        ].iter() {
+            right.hash(&mut hasher);
+        }
+
+        // Only hashed when pawns of the side to move could capture onto the target
+        let en_passant = self.en_passant.filter(|&target| {
+            pawn_attacks(target, !self.white_to_move) & self.get_piece_bitboard(self.white_to_move, PAWN) != 0
+        });
+
+        en_passant.hash(&mut hasher);
+
+        hasher.finish()
+    }
+}
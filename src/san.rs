@@ -0,0 +1,262 @@
+// Standard Algebraic Notation, e.g. "Nbd7", "exd5", "O-O", "e8=Q+" and "Qh4#".
+// Moves are always written and read against the position they are played in.
+
+use std::error::Error;
+use std::fmt;
+use bitboard::*;
+use position::{Move, Position};
+use square::Square;
+
+#[derive(Clone)]
+#[derive(PartialEq)]
+#[derive(Debug)]
+pub enum SanError {
+    // Not shaped like a SAN move at all
+    InvalidSyntax(String),
+    // Well formed, but no legal move in the position matches it
+    IllegalMove(String),
+    // More than one legal move matches, so it needs more disambiguation
+    AmbiguousMove(String),
+}
+
+impl fmt::Display for SanError {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        match *self {
+            SanError::InvalidSyntax(ref san) => write!(f, "'{}' is not a SAN move", san),
+            SanError::IllegalMove(ref san) => write!(f, "'{}' is not a legal move here", san),
+            SanError::AmbiguousMove(ref san) => write!(f, "'{}' matches more than one legal move", san),
+        }
+    }
+}
+
+impl Error for SanError {}
+
+fn is_castling(kind: usize, chess_move: Move) -> bool {
+    kind == KING && (chess_move.get_from() % 8).abs_diff(chess_move.get_to() % 8) == 2
+}
+
+impl Position {
+    fn get_moving_kind(&self, chess_move: Move) -> Option<usize> {
+        self.piece_at(chess_move.get_from()).and_then(|piece| kind_from_name(&piece.name))
+    }
+
+    // Pawns moving diagonally capture, even onto the empty en passant square
+    fn is_capture(&self, chess_move: Move, kind: usize) -> bool {
+        let from = chess_move.get_from();
+        let to = chess_move.get_to();
+
+        self.piece_at(to).is_some() || (kind == PAWN && from % 8 != to % 8)
+    }
+
+    // The move is expected to be legal in this position
+    pub fn to_san(&self, chess_move: Move) -> String {
+        let from = chess_move.get_from();
+        let to = chess_move.get_to();
+
+        let kind = match self.get_moving_kind(chess_move) {
+            Some(kind) => kind,
+            None => return String::new(),
+        };
+
+        let mut san = String::new();
+
+        if is_castling(kind, chess_move) {
+            san.push_str(if to > from { "O-O" } else { "O-O-O" });
+        } else {
+            let captures = self.is_capture(chess_move, kind);
+
+            if kind == PAWN {
+                if captures {
+                    san.push((b'a' + (from % 8) as u8) as char);
+                }
+            } else {
+                san.push(PIECE_LETTERS[kind]);
+                san.push_str(&self.get_disambiguation(chess_move, kind));
+            }
+
+            if captures {
+                san.push('x');
+            }
+
+            if let Some(target) = Square::from_index(to) {
+                san.push_str(&target.to_algebraic());
+            }
+
+            if let Some(promotion) = chess_move.get_promotion() {
+                san.push('=');
+                san.push(PIECE_LETTERS[promotion]);
+            }
+        }
+
+        let mut after = self.get_scratch_copy();
+        after.make_move(chess_move);
+
+        if after.is_king_in_check(after.white_to_move) {
+            san.push(if after.get_legal_moves().is_empty() { '#' } else { '+' });
+        }
+
+        san
+    }
+
+    // The origin file, rank or both, whichever is enough to tell the move
+    // apart from other pieces of the same kind reaching the same square
+    fn get_disambiguation(&self, chess_move: Move, kind: usize) -> String {
+        let from = chess_move.get_from();
+
+        let rivals: Vec<u32> = self.get_legal_moves()
+            .into_iter()
+            .filter(|other| other.get_to() == chess_move.get_to() && other.get_from() != from)
+            .filter(|&other| self.get_moving_kind(other) == Some(kind))
+            .map(|other| other.get_from())
+            .collect();
+
+        let origin = match Square::from_index(from) {
+            Some(origin) => origin.to_algebraic(),
+            None => return String::new(),
+        };
+
+        if rivals.is_empty() {
+            String::new()
+        } else if rivals.iter().all(|&rival| rival % 8 != from % 8) {
+            origin[..1].to_string()
+        } else if rivals.iter().all(|&rival| rival / 8 != from / 8) {
+            origin[1..].to_string()
+        } else {
+            origin
+        }
+    }
+
+    // Reads a SAN move back into the legal move it describes. Check, mate and
+    // annotation suffixes such as "+", "#" or "!?" are accepted and ignored, as
+    // are "0-0" castling and promotions written without the "=".
+    pub fn parse_san(&self, san: &str) -> Result<Move, SanError> {
+        let trimmed = san.trim().trim_end_matches(['+', '#', '!', '?']);
+
+        if trimmed.is_empty() {
+            return Err(SanError::InvalidSyntax(san.to_string()));
+        }
+
+        let legal_moves = self.get_legal_moves();
+
+        let castling = match trimmed {
+            "O-O" | "0-0" => Some(true),
+            "O-O-O" | "0-0-0" => Some(false),
+            _ => None,
+        };
+
+        let candidates: Vec<Move> = match castling {
+            Some(kingside) => legal_moves.into_iter()
+                .filter(|&chess_move| {
+                    let from = chess_move.get_from();
+                    let to = chess_move.get_to();
+
+                    self.get_moving_kind(chess_move) == Some(KING)
+                        && is_castling(KING, chess_move)
+                        && (to > from) == kingside
+                })
+                .collect(),
+            None => {
+                let pattern = self.parse_san_pattern(trimmed)
+                    .ok_or_else(|| SanError::InvalidSyntax(san.to_string()))?;
+
+                legal_moves.into_iter()
+                    .filter(|&chess_move| pattern.matches(self, chess_move))
+                    .collect()
+            }
+        };
+
+        match candidates.len() {
+            0 => Err(SanError::IllegalMove(san.to_string())),
+            1 => Ok(candidates[0]),
+            _ => Err(SanError::AmbiguousMove(san.to_string())),
+        }
+    }
+
+    // Splits "Nbxd7", "exd8=Q" and the like into their parts, from the back:
+    // promotion, target square, capture mark, disambiguation, piece letter
+    fn parse_san_pattern(&self, san: &str) -> Option<SanPattern> {
+        let mut chars: Vec<char> = san.chars().collect();
+
+        let kind = match chars.first().and_then(|&c| PIECE_LETTERS.iter().position(|&letter| letter == c)) {
+            Some(kind) if kind != PAWN => {
+                chars.remove(0);
+                kind
+            }
+            _ => PAWN,
+        };
+
+        let mut promotion: Option<usize> = None;
+
+        if let Some(&last) = chars.last() {
+            if let Some(promoted) = PIECE_LETTERS.iter().position(|&letter| letter == last) {
+                if kind != PAWN || promoted == PAWN || promoted == KING {
+                    return None;
+                }
+
+                promotion = Some(promoted);
+                chars.pop();
+
+                if chars.last() == Some(&'=') {
+                    chars.pop();
+                }
+            }
+        }
+
+        if chars.len() < 2 {
+            return None;
+        }
+
+        let target: String = chars.split_off(chars.len() - 2).into_iter().collect();
+        let to = Square::from_algebraic(&target)?.index();
+
+        let capture = chars.last() == Some(&'x');
+
+        if capture {
+            chars.pop();
+        }
+
+        let mut file: Option<u32> = None;
+        let mut row: Option<u32> = None;
+
+        for c in chars {
+            match c {
+                'a'..='h' if file.is_none() && row.is_none() => file = Some(c as u32 - 'a' as u32),
+                '1'..='8' if row.is_none() => row = Some(8 - (c as u32 - '0' as u32)),
+                _ => return None,
+            }
+        }
+
+        // A pawn only leaves its file when capturing, and then SAN names the file
+        if kind == PAWN && file.is_none() {
+            file = Some(to % 8);
+        }
+
+        Some(SanPattern { kind, to, file, row, capture, promotion })
+    }
+}
+
+// What a SAN string pins down about the move it stands for
+struct SanPattern {
+    kind: usize,
+    to: u32,
+    // Origin column and row, when given to disambiguate
+    file: Option<u32>,
+    row: Option<u32>,
+    // Whether the SAN had an "x", which has to agree with the move
+    capture: bool,
+    promotion: Option<usize>,
+}
+
+impl SanPattern {
+    fn matches(&self, position: &Position, chess_move: Move) -> bool {
+        let from = chess_move.get_from();
+
+        position.get_moving_kind(chess_move) == Some(self.kind)
+            && !is_castling(self.kind, chess_move)
+            && chess_move.get_to() == self.to
+            && chess_move.get_promotion() == self.promotion
+            && position.is_capture(chess_move, self.kind) == self.capture
+            && self.file.is_none_or(|file| from % 8 == file)
+            && self.row.is_none_or(|row| from / 8 == row)
+    }
+}
@@ -0,0 +1,105 @@
+// A square on the board, stored as its board_index: 0 is a8 in the top left
+// corner and 63 is h1. Files count from the a-file and ranks from rank 1, both
+// starting at 0, so a1 is file 0, rank 0.
+
+use std::fmt;
+
+#[derive(Clone)]
+#[derive(Copy)]
+#[derive(PartialEq)]
+#[derive(Eq)]
+#[derive(Hash)]
+#[derive(Debug)]
+pub struct Square(u32);
+
+impl Square {
+    pub fn from_index(index: u32) -> Option<Square> {
+        if index < 64 { Some(Square(index)) } else { None }
+    }
+
+    pub fn from_file_rank(file: u32, rank: u32) -> Option<Square> {
+        if file < 8 && rank < 8 { Some(Square((7 - rank) * 8 + file)) } else { None }
+    }
+
+    // Reads names such as "e4". Anything else, including "-", gives None.
+    pub fn from_algebraic(name: &str) -> Option<Square> {
+        let mut chars = name.chars();
+        let file = chars.next()?;
+        let rank = chars.next()?;
+
+        if chars.next().is_some() || !('a'..='h').contains(&file) || !('1'..='8').contains(&rank) {
+            return None;
+        }
+
+        Square::from_file_rank(file as u32 - 'a' as u32, rank as u32 - '1' as u32)
+    }
+
+    pub fn to_algebraic(&self) -> String {
+        let file = (b'a' + self.file() as u8) as char;
+
+        format!("{}{}", file, self.rank() + 1)
+    }
+
+    pub fn index(&self) -> u32 {
+        self.0
+    }
+
+    pub fn file(&self) -> u32 {
+        self.0 % 8
+    }
+
+    pub fn rank(&self) -> u32 {
+        7 - self.0 / 8
+    }
+
+    // Moves `file_offset` files towards the h-file and `rank_offset` ranks
+    // towards rank 8. None when that leaves the board.
+    pub fn offset(&self, file_offset: i32, rank_offset: i32) -> Option<Square> {
+        let file = self.file() as i32 + file_offset;
+        let rank = self.rank() as i32 + rank_offset;
+
+        if !(0..8).contains(&file) || !(0..8).contains(&rank) {
+            return None;
+        }
+
+        Square::from_file_rank(file as u32, rank as u32)
+    }
+
+    // The square drawn under a point of a board whose top left corner is at
+    // (0, 0). Without `flipped` white sits at the bottom, with a8 top left.
+    pub fn from_pixel(x: f64, y: f64, tile_len: f64, flipped: bool) -> Option<Square> {
+        if x < 0.0 || y < 0.0 || tile_len <= 0.0 {
+            return None;
+        }
+
+        let column = (x / tile_len) as u32;
+        let row = (y / tile_len) as u32;
+
+        if column >= 8 || row >= 8 {
+            return None;
+        }
+
+        if flipped {
+            Square::from_index((7 - row) * 8 + (7 - column))
+        } else {
+            Square::from_index(row * 8 + column)
+        }
+    }
+
+    // Top left corner of the square's tile, the inverse of from_pixel
+    pub fn to_pixel(&self, tile_len: f64, flipped: bool) -> (f64, f64) {
+        let (column, row) = if flipped {
+            (7 - self.0 % 8, 7 - self.0 / 8)
+        } else {
+            (self.0 % 8, self.0 / 8)
+        };
+
+        (column as f64 * tile_len, row as f64 * tile_len)
+    }
+}
+
+impl fmt::Display for Square {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        write!(f, "{}", self.to_algebraic())
+    }
+}
@@ -4,6 +4,7 @@ use graphics::{clear, color, Rectangle, Text, Transformed};
 use graphics::rectangle::rectangle_by_corners;
 use opengl_graphics::{GlGraphics, GlyphCache};
 use piston::input::RenderArgs;
+use chess_engine::position::GameResult;
 
 pub struct Timer {
     pub white_turn: bool,
@@ -44,7 +45,21 @@ impl Timer {
             );
 
             Text::new_color([0.0, 0.0, 0.0, 1.0], 32)
-                .draw(Self::format_string_time(self.black_time).as_str(), glyphs, &c.draw_state, white_transform_text, g).unwrap();
+                .draw(Self::format_string_time(self.white_time).as_str(), glyphs, &c.draw_state, white_transform_text, g).unwrap();
+        });
+    }
+
+    // Shown in the side panel between the two clocks once the game is over
+    pub fn draw_result(&self, gl: &mut GlGraphics, r: &RenderArgs, glyphs: &mut GlyphCache, result: &GameResult) {
+        gl.draw(r.viewport(), |c, g| {
+            let headline_transform = c.transform.trans(830.0, 360.0);
+            let reason_transform = c.transform.trans(830.0, 395.0);
+
+            Text::new_color([1.0, 1.0, 1.0, 1.0], 26)
+                .draw(result.headline(), glyphs, &c.draw_state, headline_transform, g).unwrap();
+
+            Text::new_color([1.0, 1.0, 1.0, 1.0], 20)
+                .draw(result.reason(), glyphs, &c.draw_state, reason_transform, g).unwrap();
         });
     }
 
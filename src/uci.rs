@@ -0,0 +1,78 @@
+// UCI long algebraic notation: origin and target square, plus the lowercase
+// letter of the promotion piece, e.g. "e2e4" or "e7e8q". Castling is written
+// as the king's two square move, "e1g1", which is also how Move stores it.
+
+use std::error::Error;
+use std::fmt;
+use bitboard::*;
+use position::{Move, Position};
+use square::Square;
+
+#[derive(Clone)]
+#[derive(PartialEq)]
+#[derive(Debug)]
+pub enum UciError {
+    // Not two squares and an optional promotion letter
+    InvalidSyntax(String),
+    // Well formed, but not a legal move in the position
+    IllegalMove(String),
+}
+
+impl fmt::Display for UciError {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        match *self {
+            UciError::InvalidSyntax(ref uci) => write!(f, "'{}' is not a UCI move", uci),
+            UciError::IllegalMove(ref uci) => write!(f, "'{}' is not a legal move here", uci),
+        }
+    }
+}
+
+impl Error for UciError {}
+
+impl Move {
+    pub fn to_uci(&self) -> String {
+        let squares = [self.get_from(), self.get_to()];
+        let mut uci: String = squares.iter()
+            .filter_map(|&index| Square::from_index(index))
+            .map(|square| square.to_algebraic())
+            .collect();
+
+        if let Some(promotion) = self.get_promotion() {
+            uci.push(PIECE_LETTERS[promotion].to_ascii_lowercase());
+        }
+
+        uci
+    }
+}
+
+impl Position {
+    // The legal move the UCI string stands for. A promotion has to name its piece.
+    pub fn parse_uci(&self, uci: &str) -> Result<Move, UciError> {
+        let invalid = || UciError::InvalidSyntax(uci.to_string());
+
+        if !uci.is_ascii() || !(4..=5).contains(&uci.len()) {
+            return Err(invalid());
+        }
+
+        let from = Square::from_algebraic(&uci[0..2]).ok_or_else(invalid)?.index();
+        let to = Square::from_algebraic(&uci[2..4]).ok_or_else(invalid)?.index();
+
+        let promotion = match uci[4..].chars().next() {
+            Some(letter) => match PIECE_LETTERS.iter().position(|&piece| piece.to_ascii_lowercase() == letter) {
+                Some(kind) if kind != PAWN && kind != KING => Some(kind),
+                _ => return Err(invalid()),
+            },
+            None => None,
+        };
+
+        // get_legal_moves_from answers for either colour
+        if self.piece_at(from).is_none_or(|piece| piece.white != self.white_to_move) {
+            return Err(UciError::IllegalMove(uci.to_string()));
+        }
+
+        self.get_legal_moves_from(from)
+            .into_iter()
+            .find(|chess_move| chess_move.get_to() == to && chess_move.get_promotion() == promotion)
+            .ok_or_else(|| UciError::IllegalMove(uci.to_string()))
+    }
+}
@@ -0,0 +1,93 @@
+// EPD records: the position fields, the standard opcodes, round trips
+// through to_epd and the errors with their columns.
+
+extern crate chess_engine;
+
+use chess_engine::epd::{Epd, EpdError, EpdRecord, Operation};
+use chess_engine::fen::FenError;
+
+fn parse(epd: &str) -> Result<EpdRecord, EpdError> {
+    Epd { epd_string: epd.to_string() }.parse()
+}
+
+#[test]
+fn reads_position_and_operations() {
+    let record = parse(
+        "r1bqkb1r/pppp1ppp/2n2n2/4p2Q/2B1P3/8/PPPP1PPP/RNB1K1NR w KQkq - bm Qxf7#; id \"mate.001\"; acd 4; ce 32000;"
+    ).unwrap();
+
+    assert!(record.position.white_to_move);
+    assert_eq!(record.position.to_fen(), "r1bqkb1r/pppp1ppp/2n2n2/4p2Q/2B1P3/8/PPPP1PPP/RNB1K1NR w KQkq - 0 1");
+    assert_eq!(record.get_best_moves(), vec!["Qxf7#"]);
+    assert_eq!(record.get_id(), Some("mate.001"));
+    assert_eq!(record.get_depth(), Some(4));
+    assert_eq!(record.get_evaluation(), Some(32000));
+    assert_eq!(record.operations.len(), 4);
+}
+
+#[test]
+fn several_operands_and_comments() {
+    let record = parse("4k3/8/8/8/8/8/8/R3K3 w Q - am Kd2 Ke2; pv O-O-O Kd7 Rd1+; c0 \"a rook; up\"; c7 \"\";").unwrap();
+
+    assert_eq!(record.get_avoid_moves(), vec!["Kd2", "Ke2"]);
+    assert_eq!(record.get_principal_variation(), vec!["O-O-O", "Kd7", "Rd1+"]);
+    assert_eq!(record.get_comment(0), Some("a rook; up"));
+    assert_eq!(record.get_comment(7), Some(""));
+    assert_eq!(record.get_comment(1), None);
+    assert!(record.get_best_moves().is_empty());
+}
+
+#[test]
+fn no_operations() {
+    let record = parse("4k3/8/8/8/8/8/8/4K3 b - -").unwrap();
+
+    assert!(!record.position.white_to_move);
+    assert!(record.operations.is_empty());
+    assert_eq!(record.to_epd(), "4k3/8/8/8/8/8/8/4K3 b - -");
+}
+
+#[test]
+fn round_trips() {
+    for epd in [
+        "r1bqkb1r/pppp1ppp/2n2n2/4p2Q/2B1P3/8/PPPP1PPP/RNB1K1NR w KQkq - bm Qxf7#; id \"mate.001\";",
+        "rnbqkbnr/ppp1p1pp/8/3pPp2/8/8/PPPP1PPP/RNBQKBNR w KQkq f6 bm exf6; acd 12; ce -15;",
+        "4k3/8/8/8/8/8/8/R3K3 w Q - pv O-O-O Kd7; c0 \"a rook; up\"; c1 \"\";",
+    ].iter() {
+        assert_eq!(parse(epd).unwrap().to_epd(), *epd);
+    }
+}
+
+#[test]
+fn set_operation_replaces_by_opcode() {
+    let mut record = parse("4k3/8/8/8/8/8/8/4K3 w - - bm Kd2; id \"first\";").unwrap();
+
+    record.set_operation(Operation::new("id", vec!["second".to_string()]));
+    record.set_operation(Operation::new("acd", vec!["3".to_string()]));
+
+    assert_eq!(record.to_epd(), "4k3/8/8/8/8/8/8/4K3 w - - bm Kd2; id \"second\"; acd 3;");
+}
+
+#[test]
+fn position_errors_come_from_fen() {
+    assert_eq!(
+        parse("4k3/8/8/8/8/8/8/4K3 x - - id \"x\";").unwrap_err(),
+        EpdError::Fen(FenError::BadSideToMove { column: 21 }),
+    );
+}
+
+#[test]
+fn operation_errors() {
+    let cases = [
+        ("4k3/8/8/8/8/8/8/4K3 w - - bm Kd2", EpdError::MissingSemicolon { column: 27 }),
+        ("4k3/8/8/8/8/8/8/4K3 w - - id \"open;", EpdError::UnterminatedString { column: 30 }),
+        ("4k3/8/8/8/8/8/8/4K3 w - - \"id\" x;", EpdError::BadOpcode { column: 27 }),
+        ("4k3/8/8/8/8/8/8/4K3 w - - 1bm Kd2;", EpdError::BadOpcode { column: 27 }),
+        ("4k3/8/8/8/8/8/8/4K3 w - - bm Kd2;;", EpdError::BadOpcode { column: 34 }),
+        ("4k3/8/8/8/8/8/8/4K3 w - - acd deep;", EpdError::BadOperand { opcode: "acd".to_string(), column: 31 }),
+        ("4k3/8/8/8/8/8/8/4K3 w - - ce;", EpdError::BadOperand { opcode: "ce".to_string(), column: 29 }),
+    ];
+
+    for (epd, expected) in cases.iter() {
+        assert_eq!(&parse(epd).unwrap_err(), expected, "{:?}", epd);
+    }
+}
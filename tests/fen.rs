@@ -0,0 +1,123 @@
+// FEN import and export: round trips through to_fen, and one case for every
+// FenError with the column it points at.
+
+extern crate chess_engine;
+
+use chess_engine::fen::{Fen, FenError, START_POSITION};
+use chess_engine::position::Position;
+
+fn parse(fen: &str) -> Result<Position, FenError> {
+    Fen { fen_string: fen.to_string() }.parse()
+}
+
+fn assert_error(fen: &str, expected: FenError) {
+    match parse(fen) {
+        Ok(_) => panic!("{:?} parsed", fen),
+        Err(error) => assert_eq!(error, expected, "{:?}", fen),
+    }
+}
+
+#[test]
+fn round_trips() {
+    for fen in [
+        START_POSITION,
+        "r3k2r/p1ppqpb1/bn2pnp1/3PN3/1p2P3/2N2Q1p/PPPBBPPP/R3K2R w KQkq - 0 1",
+        "rnbqkbnr/ppp1p1pp/8/3pPp2/8/8/PPPP1PPP/RNBQKBNR w KQkq f6 0 3",
+        "8/2p5/3p4/KP5r/1R3p1k/8/4P1P1/8 b - - 12 40",
+        "r3k3/8/8/8/8/8/8/4K2R w Kq - 0 1",
+    ].iter() {
+        assert_eq!(parse(fen).unwrap().to_fen(), *fen);
+    }
+}
+
+#[test]
+fn move_counters_are_optional() {
+    let position = parse("4k3/8/8/8/8/8/8/4K3 b - -").unwrap();
+
+    assert!(!position.white_to_move);
+    assert_eq!(position.to_fen(), "4k3/8/8/8/8/8/8/4K3 b - - 0 1");
+}
+
+#[test]
+fn start_position_layout() {
+    let position = parse(START_POSITION).unwrap();
+
+    // a8 is board_index 0, h1 is 63
+    assert_eq!(position.piece_at(0).unwrap().name, "Rook");
+    assert!(!position.piece_at(0).unwrap().white);
+    assert_eq!(position.piece_at(60).unwrap().name, "King");
+    assert!(position.piece_at(60).unwrap().white);
+    assert_eq!(position.piece_at(3).unwrap().name, "Queen");
+    assert_eq!(position.get_legal_moves().len(), 20);
+}
+
+#[test]
+fn interpret_is_lenient() {
+    let position = Fen { fen_string: "4k3/8/8/8/8/8/8/4K3".to_string() }.interpret();
+
+    assert!(position.white_to_move);
+    assert_eq!(position.to_fen(), "4k3/8/8/8/8/8/8/4K3 w - - 0 1");
+}
+
+#[test]
+fn missing_and_extra_fields() {
+    assert_error("4k3/8/8/8/8/8/8/4K3 w -", FenError::MissingField { field: 4, column: 24 });
+    assert_error("", FenError::MissingField { field: 1, column: 1 });
+    assert_error("4k3/8/8/8/8/8/8/4K3 w - - 0 1 x", FenError::TooManyFields { column: 31 });
+}
+
+#[test]
+fn placement_errors() {
+    assert_error("4k3/8/8/8/8/8/4K3 w - -", FenError::BadRankCount { ranks: 7, column: 1 });
+    assert_error("4k3/8/8/45/8/8/8/4K3 w - -", FenError::BadRankLength { rank: 5, squares: 9, column: 9 });
+    assert_error("4k3/8/8/7/8/8/8/4K3 w - -", FenError::BadRankLength { rank: 5, squares: 7, column: 9 });
+    assert_error("4k3/8/8/8/3X4/8/8/4K3 w - -", FenError::UnknownPieceLetter { letter: 'X', column: 12 });
+    assert_error("4k3/8/8/8/8/8/8/8 w - -", FenError::MissingKing { white: true, column: 1 });
+    assert_error("4k3/8/8/8/8/8/8/3KK3 w - -", FenError::TooManyKings { white: true, column: 1 });
+    assert_error("8/8/8/8/8/8/8/4K3 w - -", FenError::MissingKing { white: false, column: 1 });
+    assert_error("4kk2/8/8/8/8/8/8/4K3 w - -", FenError::TooManyKings { white: false, column: 1 });
+    assert_error("P3k3/8/8/8/8/8/8/4K3 w - -", FenError::PawnOnBackRank { column: 1 });
+    assert_error("4k3/8/8/8/8/8/8/4K2p w - -", FenError::PawnOnBackRank { column: 20 });
+}
+
+#[test]
+fn side_to_move_errors() {
+    assert_error("4k3/8/8/8/8/8/8/4K3 W - -", FenError::BadSideToMove { column: 21 });
+}
+
+#[test]
+fn castling_errors() {
+    assert_error("r3k2r/8/8/8/8/8/8/R3K2R w KX -", FenError::BadCastlingLetter { letter: 'X', column: 28 });
+    assert_error("r3k2r/8/8/8/8/8/8/R3K2R w KK -", FenError::BadCastlingLetter { letter: 'K', column: 28 });
+    // The white king is off e1, so neither of its rights is possible
+    assert_error("r3k2r/8/8/8/8/8/8/R4K1R w Kq -", FenError::ImpossibleCastlingRights { letter: 'K', column: 27 });
+    // No rook on a8
+    assert_error("4k2r/8/8/8/8/8/8/R3K2R w KQkq -", FenError::ImpossibleCastlingRights { letter: 'q', column: 29 });
+}
+
+#[test]
+fn en_passant_errors() {
+    // Not a square at all
+    assert_error("4k3/8/8/8/4P3/8/8/4K3 b - e9", FenError::BadEnPassantSquare { column: 27 });
+    // Wrong rank for the side that just moved
+    assert_error("4k3/8/8/8/4P3/8/8/4K3 b - e6", FenError::BadEnPassantSquare { column: 27 });
+    // No pawn in front of the square
+    assert_error("4k3/8/8/8/8/8/8/4K3 b - e3", FenError::BadEnPassantSquare { column: 25 });
+
+    assert!(parse("4k3/8/8/8/4P3/8/8/4K3 b - e3").is_ok());
+}
+
+#[test]
+fn move_counter_errors() {
+    assert_error("4k3/8/8/8/8/8/8/4K3 w - - x 1", FenError::BadMoveCounter { field: 5, column: 27 });
+    assert_error("4k3/8/8/8/8/8/8/4K3 w - - 0 0", FenError::BadMoveCounter { field: 6, column: 29 });
+    assert_error("4k3/8/8/8/8/8/8/4K3 w - - -1 1", FenError::BadMoveCounter { field: 5, column: 27 });
+}
+
+#[test]
+fn errors_report_their_column() {
+    let error = parse("4k3/8/8/8/3X4/8/8/4K3 w - -").unwrap_err();
+
+    assert_eq!(error.get_column(), 12);
+    assert_eq!(error.to_string(), "unknown piece letter 'X' (column 12)");
+}
@@ -0,0 +1,96 @@
+// Node counts for the reference positions from the chess programming wiki
+// (https://www.chessprogramming.org/Perft_Results). Depths are kept low enough
+// for a debug build.
+
+extern crate chess_engine;
+
+use chess_engine::fen::Fen;
+use chess_engine::position::Position;
+
+fn position(fen: &str) -> Position {
+    Fen { fen_string: fen.to_string() }.parse().unwrap()
+}
+
+fn assert_perft(mut position: Position, expected: &[u64]) {
+    for (depth, &nodes) in expected.iter().enumerate() {
+        assert_eq!(position.perft(depth as u32 + 1), nodes, "perft({})", depth + 1);
+    }
+}
+
+#[test]
+fn start_position() {
+    assert_perft(
+        position("rnbqkbnr/pppppppp/8/8/8/8/PPPPPPPP/RNBQKBNR w KQkq - 0 1"),
+        &[20, 400, 8902, 197281],
+    );
+}
+
+#[test]
+fn kiwipete() {
+    assert_perft(
+        position("r3k2r/p1ppqpb1/bn2pnp1/3PN3/1p2P3/2N2Q1p/PPPBBPPP/R3K2R w KQkq - 0 1"),
+        &[48, 2039, 97862],
+    );
+}
+
+#[test]
+fn position_3() {
+    assert_perft(
+        position("8/2p5/3p4/KP5r/1R3p1k/8/4P1P1/8 w - - 0 1"),
+        &[14, 191, 2812, 43238],
+    );
+}
+
+#[test]
+fn position_4() {
+    assert_perft(
+        position("r3k2r/Pppp1ppp/1b3nbN/nP6/BBP1P3/q4N2/Pp1P2PP/R2Q1RK1 w kq - 0 1"),
+        &[6, 264, 9467],
+    );
+}
+
+#[test]
+fn position_4_mirrored() {
+    assert_perft(
+        position("r2q1rk1/pP1p2pp/Q4n2/bbp1p3/Np6/1B3NBn/pPPP1PPP/R3K2R b KQ - 0 1"),
+        &[6, 264, 9467],
+    );
+}
+
+#[test]
+fn position_5() {
+    assert_perft(
+        position("rnbq1k1r/pp1Pbppp/2p5/8/2B5/8/PPP1NnPP/RNBQK2R w KQ - 1 8"),
+        &[44, 1486, 62379],
+    );
+}
+
+#[test]
+fn position_6() {
+    assert_perft(
+        position("r4rk1/1pp1qppp/p1np1n2/2b1p1B1/2B1P1b1/P1NP1N2/1PP1QPPP/R4RK1 w - - 0 10"),
+        &[46, 2079, 89890],
+    );
+}
+
+#[test]
+fn divide_adds_up_to_perft() {
+    let mut start = position("rnbqkbnr/pppppppp/8/8/8/8/PPPPPPPP/RNBQKBNR w KQkq - 0 1");
+    let counts = start.divide(3);
+
+    assert_eq!(counts.len(), 20);
+    assert_eq!(counts.iter().map(|&(_, nodes)| nodes).sum::<u64>(), 8902);
+}
+
+#[test]
+fn perft_leaves_the_position_unchanged() {
+    let mut kiwipete = position("r3k2r/p1ppqpb1/bn2pnp1/3PN3/1p2P3/2N2Q1p/PPPBBPPP/R3K2R w KQkq - 0 1");
+    let fen = kiwipete.to_fen();
+    let hash = kiwipete.get_position_hash();
+
+    kiwipete.perft(3);
+
+    assert_eq!(kiwipete.to_fen(), fen);
+    assert_eq!(kiwipete.get_position_hash(), hash);
+    assert!(kiwipete.unmake_move().is_none());
+}
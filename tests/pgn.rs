@@ -0,0 +1,253 @@
+// PGN import and export: tags, comments, NAGs, nested variations, several
+// games in one stream, and where errors are reported.
+
+extern crate chess_engine;
+
+use chess_engine::fen::{Fen, START_POSITION};
+use chess_engine::pgn::{Game, MoveNode, PgnError, PgnErrorKind, PgnReader};
+use chess_engine::san::SanError;
+
+fn read_all(pgn: &str) -> Vec<Result<Game, PgnError>> {
+    PgnReader::new(pgn.as_bytes()).collect()
+}
+
+fn read_one(pgn: &str) -> Game {
+    let mut games = read_all(pgn);
+
+    assert_eq!(games.len(), 1);
+    games.remove(0).unwrap()
+}
+
+fn read_error(pgn: &str) -> PgnError {
+    match read_all(pgn).into_iter().next() {
+        Some(Err(error)) => error,
+        other => panic!("expected an error, got {:?}", other),
+    }
+}
+
+fn sans(line: &[MoveNode]) -> Vec<&str> {
+    line.iter().map(|node| node.san.as_str()).collect()
+}
+
+#[test]
+fn tags_and_main_line() {
+    let game = read_one(
+        "[Event \"Casual game\"]\n\
+         [White \"Morphy, Paul\"]\n\
+         [Black \"Duke \\\"Karl\\\"\"]\n\
+         [Result \"1-0\"]\n\
+         \n\
+         1. e4 e5 2. Nf3 d6 3.d4 Bg4 4. dxe5 Bxf3 5. Qxf3 dxe5 6. Bc4 Nf6 7. Qb3 Qe7\n\
+         8. Nc3 c6 9. Bg5 b5 10. Nxb5 cxb5 11. Bxb5+ Nbd7 12. O-O-O Rd8\n\
+         13. Rxd7 Rxd7 14. Rd1 Qe6 15. Bxd7+ Nxd7 16. Qb8+ Nxb8 17. Rd8# 1-0\n",
+    );
+
+    assert_eq!(game.get_tag("Event"), Some("Casual game"));
+    assert_eq!(game.get_tag("White"), Some("Morphy, Paul"));
+    assert_eq!(game.get_tag("Black"), Some("Duke \"Karl\""));
+    assert_eq!(game.get_tag("Result"), Some("1-0"));
+    assert_eq!(game.get_tag("Site"), None);
+    assert_eq!(game.moves.len(), 33);
+    assert_eq!(sans(&game.moves[..4]), vec!["e4", "e5", "Nf3", "d6"]);
+    assert_eq!(game.moves.last().unwrap().san, "Rd8#");
+    assert_eq!(game.first_move_number, 1);
+    assert!(game.white_moves_first);
+}
+
+#[test]
+fn moves_are_stored_as_canonical_san() {
+    let game = read_one("1. e4! e5 2. Nf3 Nc6 3. Bb5 a6 4. Ba4 Nf6 5. 0-0 Be7 6. Re1?! b5 7. Bb3 d6 *");
+
+    // Castling with zeros becomes "O-O", and suffixes turn into NAGs
+    assert_eq!(game.moves[8].san, "O-O");
+    assert_eq!(game.moves[0].san, "e4");
+    assert_eq!(game.moves[0].nags, vec![1]);
+    assert_eq!(game.moves[10].nags, vec![6]);
+    assert_eq!(read_error("1. e2-e4 *").kind, PgnErrorKind::BadMove(SanError::InvalidSyntax("e2-e4".to_string())));
+}
+
+#[test]
+fn comments_and_nags() {
+    let game = read_one(
+        "{Opening comment} 1. e4 $1 {Best by test} e5 ; a rest of line comment\n\
+         2. Nf3!? {multi\n\
+         line} Nc6 $2 $18 *\n",
+    );
+
+    assert_eq!(game.moves[0].comments_before, vec!["Opening comment"]);
+    assert_eq!(game.moves[0].nags, vec![1]);
+    assert_eq!(game.moves[0].comments, vec!["Best by test"]);
+    assert_eq!(game.moves[1].comments, vec!["a rest of line comment"]);
+    assert_eq!(game.moves[2].nags, vec![5]);
+    assert_eq!(game.moves[2].comments, vec!["multi line"]);
+    assert_eq!(game.moves[3].nags, vec![2, 18]);
+    assert_eq!(game.get_tag("Result"), Some("*"));
+}
+
+#[test]
+fn nested_variations() {
+    let game = read_one(
+        "1. e4 e5 (1... c5 2. Nf3 (2. c3 d5) 2... d6) (1... e6) 2. Nf3 (2. f4 exf4) Nc6 *",
+    );
+
+    assert_eq!(sans(&game.moves), vec!["e4", "e5", "Nf3", "Nc6"]);
+
+    let black_replies = &game.moves[1].variations;
+
+    assert_eq!(black_replies.len(), 2);
+    assert_eq!(sans(&black_replies[0]), vec!["c5", "Nf3", "d6"]);
+    assert_eq!(sans(&black_replies[1]), vec!["e6"]);
+    assert_eq!(sans(&black_replies[0][1].variations[0]), vec!["c3", "d5"]);
+    assert_eq!(sans(&game.moves[2].variations[0]), vec!["f4", "exf4"]);
+}
+
+#[test]
+fn streams_several_games() {
+    let pgn = "[Event \"First\"]\n\
+               \n\
+               1. e4 e5 1/2-1/2\n\
+               \n\
+               [Event \"Second\"]\n\
+               [SetUp \"1\"]\n\
+               [FEN \"4k3/8/8/8/8/8/8/R3K3 b Q - 0 30\"]\n\
+               \n\
+               30... Kd7 31. O-O-O+ 1-0\n\
+               \n\
+               [Event \"Third\"]\n\
+               \n\
+               1. d4 *\n";
+
+    let games: Vec<Game> = read_all(pgn).into_iter().map(|game| game.unwrap()).collect();
+
+    assert_eq!(games.len(), 3);
+    assert_eq!(games[0].get_tag("Event"), Some("First"));
+    assert_eq!(games[0].get_tag("Result"), Some("1/2-1/2"));
+    assert_eq!(games[1].first_move_number, 30);
+    assert!(!games[1].white_moves_first);
+    assert_eq!(sans(&games[1].moves), vec!["Kd7", "O-O-O+"]);
+    assert_eq!(games[2].get_tag("Event"), Some("Third"));
+}
+
+#[test]
+fn streams_games_without_tags() {
+    let games = read_all("1. e4 *\n\n1. d4 d5 {drawn} 1/2-1/2\n1. c4 1-0\n\n1. Nf3 e5 2. Ke3 *\n1. g3 * {the end}\n");
+
+    // A comment after the last result is not a game of its own
+    assert_eq!(games.len(), 5);
+    assert_eq!(sans(&games[0].as_ref().unwrap().moves), vec!["e4"]);
+    assert_eq!(sans(&games[1].as_ref().unwrap().moves), vec!["d4", "d5"]);
+    assert_eq!(games[1].as_ref().unwrap().moves[1].comments, vec!["drawn"]);
+    assert_eq!(games[2].as_ref().unwrap().get_tag("Result"), Some("1-0"));
+
+    // The bad third move is reported where it stands, and the game after it is still read
+    let error = games[3].as_ref().unwrap_err();
+
+    assert_eq!((error.game, error.line, error.column), (4, 6, 14));
+    assert_eq!(sans(&games[4].as_ref().unwrap().moves), vec!["g3"]);
+}
+
+#[test]
+fn a_bad_game_does_not_stop_the_stream() {
+    let pgn = "[Event \"Good\"]\n\n1. e4 *\n\n[Event \"Bad\"]\n\n1. e5 *\n\n[Event \"Also good\"]\n\n1. d4 *\n";
+    let games = read_all(pgn);
+
+    assert_eq!(games.len(), 3);
+    assert!(games[0].is_ok());
+    assert_eq!(games[1].as_ref().unwrap_err().game, 2);
+    assert_eq!(games[2].as_ref().unwrap().get_tag("Event"), Some("Also good"));
+}
+
+#[test]
+fn export_round_trips() {
+    let pgn = "[Event \"Round trip\"]\n[Result \"*\"]\n\n\
+               {Start} 1. e4 $1 e5 (1... c5 {Sicilian} 2. Nf3) 2. Nf3 Nc6 {Knights} 3. Bb5 *\n";
+    let game = read_one(pgn);
+    let again = read_one(&game.to_pgn());
+
+    assert_eq!(again.to_pgn(), game.to_pgn());
+    assert_eq!(sans(&again.moves), vec!["e4", "e5", "Nf3", "Nc6", "Bb5"]);
+    assert_eq!(again.moves[0].comments_before, vec!["Start"]);
+    assert_eq!(again.moves[1].variations[0][0].comments, vec!["Sicilian"]);
+}
+
+#[test]
+fn exported_comments_stay_closed() {
+    let mut game = read_one("1. e4 e5 *");
+
+    game.moves[0].comments.push("x } y".to_string());
+    game.moves[0].comments_before.push("}{".to_string());
+
+    let again = read_one(&game.to_pgn());
+
+    assert_eq!(sans(&again.moves), vec!["e4", "e5"]);
+    assert_eq!(again.moves[0].comments, vec!["x  y"]);
+    assert_eq!(again.moves[0].comments_before, vec!["{"]);
+}
+
+#[test]
+fn recorded_game_exports_the_seven_tag_roster() {
+    let start = Fen { fen_string: START_POSITION.to_string() }.parse().unwrap();
+    let mut position = start.clone();
+    let mut game = Game::new(&start);
+
+    for san in ["e4", "e5", "Qh5", "Nc6", "Bc4", "Nf6", "Qxf7#"].iter() {
+        let chess_move = position.parse_san(san).unwrap();
+
+        game.record_move(&position, chess_move);
+        position.make_move(chess_move);
+    }
+
+    game.set_result(position.get_game_result().as_ref());
+
+    let pgn = game.to_pgn();
+    let names: Vec<&str> = pgn.lines()
+        .take_while(|line| line.starts_with('['))
+        .map(|line| line[1..].split(' ').next().unwrap())
+        .collect();
+
+    assert_eq!(names, vec!["Event", "Site", "Date", "Round", "White", "Black", "Result"]);
+    assert!(pgn.ends_with("1. e4 e5 2. Qh5 Nc6 3. Bc4 Nf6 4. Qxf7# 1-0\n"), "{}", pgn);
+}
+
+#[test]
+fn error_positions() {
+    let cases = [
+        ("1. e4 e5 2. Ke3 *", 1, 13, PgnErrorKind::BadMove(SanError::IllegalMove("Ke3".to_string()))),
+        ("1. e4\n2. Nf3 {never closed\n", 2, 8, PgnErrorKind::UnterminatedComment),
+        ("[Event \"open\n\n1. e4 *", 1, 8, PgnErrorKind::UnterminatedString),
+        ("[Event]\n\n1. e4 *", 1, 1, PgnErrorKind::BadTag),
+        ("1. e4 $ e5 *", 1, 7, PgnErrorKind::BadNag),
+        ("1. e4 e5 & *", 1, 10, PgnErrorKind::UnexpectedCharacter('&')),
+        ("(1. d4) 1. e4 *", 1, 1, PgnErrorKind::UnexpectedToken("(".to_string())),
+        ("1. e4 ) *", 1, 7, PgnErrorKind::UnexpectedToken(")".to_string())),
+        ("1. e4 (1. d4 d5 *", 1, 7, PgnErrorKind::UnclosedVariation),
+    ];
+
+    for (pgn, line, column, kind) in cases.iter() {
+        let error = read_error(pgn);
+
+        assert_eq!((error.game, error.line, error.column), (1, *line, *column), "{:?}", pgn);
+        assert_eq!(&error.kind, kind, "{:?}", pgn);
+    }
+}
+
+#[test]
+fn bad_fen_tag_points_at_the_tag() {
+    let error = read_error("[Event \"x\"]\n[FEN \"8/8/8/8/8/8/8/8 w - - 0 1\"]\n\n*\n");
+
+    assert_eq!((error.line, error.column), (2, 1));
+
+    match error.kind {
+        PgnErrorKind::BadFen(_) => {}
+        other => panic!("expected BadFen, got {:?}", other),
+    }
+}
+
+#[test]
+fn errors_count_games_and_lines_across_the_stream() {
+    let pgn = "[Event \"One\"]\n\n1. e4 *\n\n[Event \"Two\"]\n\n1. e4 e5\n2. Nf3 Nf6 3. Qd8 *\n";
+    let error = read_all(pgn).remove(1).unwrap_err();
+
+    assert_eq!((error.game, error.line, error.column), (2, 8, 15));
+    assert_eq!(error.to_string(), "game 2, line 8, column 15: 'Qd8' is not a legal move here");
+}
@@ -0,0 +1,179 @@
+// Game results: checkmate and stalemate from the legal move list, and the
+// automatic draws for move counts, repetitions and insufficient material.
+
+extern crate chess_engine;
+
+use chess_engine::fen::{Fen, START_POSITION};
+use chess_engine::position::{DrawReason, GameResult, Piece, Position};
+use chess_engine::square::Square;
+
+fn position(fen: &str) -> Position {
+    Fen { fen_string: fen.to_string() }.parse().unwrap()
+}
+
+#[test]
+fn checkmate() {
+    // Fool's mate
+    let black_mates = position("rnb1kbnr/pppp1ppp/8/4p3/6Pq/5P2/PPPPP2P/RNBQKBNR w KQkq - 1 3");
+    // Back rank mate
+    let white_mates = position("R5k1/5ppp/8/8/8/8/8/6K1 b - - 1 1");
+
+    assert_eq!(black_mates.get_game_result(), Some(GameResult::BlackWins));
+    assert_eq!(white_mates.get_game_result(), Some(GameResult::WhiteWins));
+    assert!(white_mates.get_legal_moves().is_empty());
+    assert!(white_mates.is_king_in_check(false));
+}
+
+#[test]
+fn stalemate() {
+    let stalemate = position("7k/5Q2/6K1/8/8/8/8/8 b - - 0 1");
+
+    assert!(!stalemate.is_king_in_check(false));
+    assert_eq!(stalemate.get_game_result(), Some(GameResult::Draw(DrawReason::Stalemate)));
+}
+
+#[test]
+fn game_goes_on() {
+    let ongoing = position("6k1/5ppp/8/8/8/8/8/R5K1 w - - 0 1");
+
+    assert_eq!(ongoing.get_game_result(), None);
+}
+
+#[test]
+fn mate_beats_the_move_count_draws() {
+    for &halfmove_clock in [100, 149, 150, 200].iter() {
+        let fen = format!("R5k1/5ppp/8/8/8/8/8/6K1 b - - {} 90", halfmove_clock);
+
+        assert_eq!(position(&fen).get_game_result(), Some(GameResult::WhiteWins), "{}", fen);
+    }
+
+    // The mating move itself is the hundredth half-move without a capture or pawn move
+    let mut before_mate = position("6k1/5ppp/8/8/8/8/8/R5K1 w - - 99 90");
+    let mate = before_mate.parse_san("Ra8#").unwrap();
+
+    before_mate.make_move(mate);
+
+    assert_eq!(before_mate.halfmove_clock, 100);
+    assert_eq!(before_mate.get_game_result(), Some(GameResult::WhiteWins));
+}
+
+// Both knights out and back, which brings back the position it started from
+fn shuffle_knights(position: &mut Position) {
+    for san in ["Nf3", "Nf6", "Ng1", "Ng8"].iter() {
+        let chess_move = position.parse_san(san).unwrap();
+
+        position.make_move(chess_move);
+    }
+}
+
+#[test]
+fn repetitions() {
+    let mut game = position(START_POSITION);
+
+    assert_eq!(game.get_repetition_count(), 1);
+
+    shuffle_knights(&mut game);
+
+    assert_eq!(game.get_repetition_count(), 2);
+    assert!(!game.is_threefold_repetition());
+    assert_eq!(game.get_game_result(), None);
+
+    shuffle_knights(&mut game);
+
+    assert_eq!(game.get_repetition_count(), 3);
+    assert!(game.is_threefold_repetition());
+    assert!(!game.is_fivefold_repetition());
+    assert_eq!(game.get_game_result(), Some(GameResult::Draw(DrawReason::ThreefoldRepetition)));
+
+    shuffle_knights(&mut game);
+    shuffle_knights(&mut game);
+
+    assert_eq!(game.get_repetition_count(), 5);
+    assert!(game.is_fivefold_repetition());
+    assert_eq!(game.get_draw_reason(), Some(DrawReason::FivefoldRepetition));
+
+    // Taking a move back also takes its position out of the count
+    game.unmake_move();
+
+    assert_eq!(game.get_repetition_count(), 4);
+}
+
+#[test]
+fn en_passant_only_counts_when_it_can_be_taken() {
+    let hash = |fen: &str| position(fen).get_position_hash();
+
+    // No black pawn next to e4, so the e3 square makes no difference
+    assert_eq!(hash("4k3/8/8/8/4P3/8/8/4K3 b - e3 0 1"), hash("4k3/8/8/8/4P3/8/8/4K3 b - - 0 1"));
+    // The d4 pawn could take on e3
+    assert_ne!(hash("4k3/8/8/8/3pP3/8/8/4K3 b - e3 0 1"), hash("4k3/8/8/8/3pP3/8/8/4K3 b - - 0 1"));
+    // Side to move and castling rights are part of the position
+    assert_ne!(hash("4k3/8/8/8/8/8/8/R3K3 w - - 0 1"), hash("4k3/8/8/8/8/8/8/R3K3 b - - 0 1"));
+    assert_ne!(hash("4k3/8/8/8/8/8/8/R3K3 w Q - 0 1"), hash("4k3/8/8/8/8/8/8/R3K3 w - - 0 1"));
+    // The move counters are not
+    assert_eq!(hash("4k3/8/8/8/8/8/8/R3K3 w - - 0 1"), hash("4k3/8/8/8/8/8/8/R3K3 w - - 40 60"));
+}
+
+#[test]
+fn move_count_draws() {
+    let at = |halfmove_clock: u32| position(&format!("4k3/8/8/8/8/8/8/R3K3 w - - {} 80", halfmove_clock));
+
+    assert!(!at(99).is_fifty_move_draw());
+    assert_eq!(at(99).get_game_result(), None);
+
+    assert!(at(100).is_fifty_move_draw());
+    assert!(!at(100).is_seventy_five_move_draw());
+    assert_eq!(at(100).get_game_result(), Some(GameResult::Draw(DrawReason::FiftyMoveRule)));
+
+    assert!(at(150).is_seventy_five_move_draw());
+    assert_eq!(at(150).get_game_result(), Some(GameResult::Draw(DrawReason::SeventyFiveMoveRule)));
+}
+
+#[test]
+fn insufficient_material() {
+    let draws = [
+        "4k3/8/8/8/8/8/8/4K3 w - - 0 1",
+        "4k3/8/8/8/8/8/8/2B1K3 w - - 0 1",
+        "4k3/8/8/8/8/8/8/1N2K3 b - - 0 1",
+        // Both bishops on dark squares
+        "5b1k/8/8/8/8/8/8/2B1K3 w - - 0 1",
+    ];
+
+    for fen in draws.iter() {
+        assert!(position(fen).is_insufficient_material(), "{}", fen);
+        assert_eq!(position(fen).get_game_result(), Some(GameResult::Draw(DrawReason::InsufficientMaterial)), "{}", fen);
+    }
+
+    let playable = [
+        // Bishops on opposite colours
+        "2b4k/8/8/8/8/8/8/2B1K3 w - - 0 1",
+        "1n2k3/8/8/8/8/8/8/1N2K3 w - - 0 1",
+        "4k3/8/8/8/8/8/8/1NB1K3 w - - 0 1",
+        "4k3/8/8/8/8/8/4P3/4K3 w - - 0 1",
+        "4k3/8/8/8/8/8/8/R3K3 w - - 0 1",
+    ];
+
+    for fen in playable.iter() {
+        assert!(!position(fen).is_insufficient_material(), "{}", fen);
+    }
+}
+
+#[test]
+fn bishop_square_colours() {
+    // a1 is dark, so a square is light when its file + rank is odd
+    let is_light = |square: Square| (square.file() + square.rank()) % 2 == 1;
+
+    for first in (0..64).filter_map(Square::from_index) {
+        for second in (0..64).filter_map(Square::from_index) {
+            if first == second {
+                continue;
+            }
+
+            let mut board = Position::empty();
+
+            board.put_piece(first.index(), &Piece::new("Bishop", true));
+            board.put_piece(second.index(), &Piece::new("Bishop", false));
+
+            assert_eq!(board.is_insufficient_material(), is_light(first) == is_light(second), "{} {}", first, second);
+        }
+    }
+}
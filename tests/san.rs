@@ -0,0 +1,171 @@
+// SAN generation and parsing: disambiguation, promotions, check and mate
+// suffixes, castling, and the errors for malformed, illegal and ambiguous moves.
+
+extern crate chess_engine;
+
+use chess_engine::fen::{Fen, START_POSITION};
+use chess_engine::position::Position;
+use chess_engine::san::SanError;
+
+fn position(fen: &str) -> Position {
+    Fen { fen_string: fen.to_string() }.parse().unwrap()
+}
+
+// The SAN of the move given in UCI, which also has to parse back to that move
+fn san_for(position: &Position, uci: &str) -> String {
+    let chess_move = position.parse_uci(uci).unwrap();
+    let san = position.to_san(chess_move);
+
+    assert_eq!(position.parse_san(&san), Ok(chess_move), "{}", san);
+
+    san
+}
+
+#[test]
+fn every_legal_move_round_trips() {
+    for fen in [
+        START_POSITION,
+        "r3k2r/p1ppqpb1/bn2pnp1/3PN3/1p2P3/2N2Q1p/PPPBBPPP/R3K2R w KQkq - 0 1",
+        "r3k2r/Pppp1ppp/1b3nbN/nP6/BBP1P3/q4N2/Pp1P2PP/R2Q1RK1 w kq - 0 1",
+        "rnbq1k1r/pp1Pbppp/2p5/8/2B5/8/PPP1NnPP/RNBQK2R w KQ - 1 8",
+    ].iter() {
+        let position = position(fen);
+
+        for chess_move in position.get_legal_moves() {
+            let san = position.to_san(chess_move);
+
+            assert_eq!(position.parse_san(&san), Ok(chess_move), "{} in {}", san, fen);
+        }
+    }
+}
+
+#[test]
+fn pieces_and_pawns() {
+    let start = position(START_POSITION);
+
+    assert_eq!(san_for(&start, "e2e4"), "e4");
+    assert_eq!(san_for(&start, "g1f3"), "Nf3");
+
+    let open = position("rnbqkbnr/ppp1pppp/8/3p4/4P3/8/PPPP1PPP/RNBQKBNR w KQkq d6 0 2");
+
+    assert_eq!(san_for(&open, "e4d5"), "exd5");
+    assert_eq!(san_for(&open, "f1b5"), "Bb5+");
+}
+
+#[test]
+fn en_passant_is_a_capture() {
+    let position = position("4k3/8/8/3pP3/8/8/8/4K3 w - d6 0 1");
+
+    assert_eq!(san_for(&position, "e5d6"), "exd6");
+    assert!(position.parse_san("d6").is_err());
+}
+
+#[test]
+fn disambiguation_by_file() {
+    let position = position("r3k3/8/8/8/8/8/8/1N2KN2 w - - 0 1");
+
+    assert_eq!(san_for(&position, "b1d2"), "Nbd2");
+    assert_eq!(san_for(&position, "f1d2"), "Nfd2");
+    // Only one knight reaches c3
+    assert_eq!(san_for(&position, "b1c3"), "Nc3");
+}
+
+#[test]
+fn disambiguation_by_rank() {
+    let position = position("4k3/8/8/R7/8/8/8/R3K3 w - - 0 1");
+
+    assert_eq!(san_for(&position, "a1a3"), "R1a3");
+    assert_eq!(san_for(&position, "a5a3"), "R5a3");
+}
+
+#[test]
+fn disambiguation_by_file_and_rank() {
+    // Three queens reach e4: h4 shares the rank with b4 and the file with h1
+    let position = position("8/k7/8/8/1Q5Q/8/8/K6Q w - - 0 1");
+
+    assert_eq!(san_for(&position, "h4e4"), "Qh4e4");
+    assert_eq!(san_for(&position, "b4e4"), "Qbe4");
+    assert_eq!(san_for(&position, "h1e4"), "Q1e4");
+}
+
+#[test]
+fn promotions() {
+    let push = position("3k4/4P3/8/8/8/8/8/4K3 w - - 0 1");
+
+    assert_eq!(san_for(&push, "e7e8q"), "e8=Q+");
+    assert_eq!(san_for(&push, "e7e8n"), "e8=N");
+    assert_eq!(push.parse_san("e8Q"), push.parse_san("e8=Q+"));
+
+    let capture = position("3r2k1/4P3/8/8/8/8/8/4K3 w - - 0 1");
+
+    assert_eq!(san_for(&capture, "e7d8r"), "exd8=R+");
+}
+
+#[test]
+fn checkmate() {
+    let position = position("rnbqkbnr/pppp1ppp/8/4p3/6P1/5P2/PPPPP2P/RNBQKBNR b KQkq g3 0 2");
+
+    assert_eq!(san_for(&position, "d8h4"), "Qh4#");
+}
+
+#[test]
+fn castling() {
+    let position = position("r3k2r/8/8/8/8/8/8/R3K2R w KQkq - 0 1");
+
+    assert_eq!(san_for(&position, "e1g1"), "O-O");
+    assert_eq!(san_for(&position, "e1c1"), "O-O-O");
+    assert_eq!(position.parse_san("0-0"), position.parse_san("O-O"));
+    assert_eq!(position.parse_san("0-0-0"), position.parse_san("O-O-O"));
+}
+
+#[test]
+fn suffixes_are_ignored_when_parsing() {
+    let start = position(START_POSITION);
+    let e4 = start.parse_uci("e2e4").unwrap();
+
+    for san in ["e4", "e4!", "e4?!", "e4!!", "e4+", " e4 "].iter() {
+        assert_eq!(start.parse_san(san), Ok(e4), "{:?}", san);
+    }
+}
+
+#[test]
+fn malformed_moves() {
+    let start = position(START_POSITION);
+
+    for san in ["", "+", "e9", "Xe4", "Ne", "e4e5e6", "e8=K", "Nf3=Q", "exx5"].iter() {
+        assert_eq!(start.parse_san(san), Err(SanError::InvalidSyntax(san.to_string())), "{:?}", san);
+    }
+}
+
+#[test]
+fn illegal_moves() {
+    let start = position(START_POSITION);
+
+    for san in ["e5", "Nf4", "Ke2", "O-O", "Bb5", "exd4", "Nxf3"].iter() {
+        assert_eq!(start.parse_san(san), Err(SanError::IllegalMove(san.to_string())), "{:?}", san);
+    }
+}
+
+#[test]
+fn capture_mark_has_to_match() {
+    let position = position("4k3/8/8/3p4/4P3/8/8/4K3 w - - 0 1");
+
+    // A pawn capture needs its file, and a quiet move can't be written as a capture
+    assert_eq!(position.parse_san("d5"), Err(SanError::IllegalMove("d5".to_string())));
+    assert_eq!(position.parse_san("Kxd2"), Err(SanError::IllegalMove("Kxd2".to_string())));
+    assert_eq!(position.parse_san("exd5"), Ok(position.parse_uci("e4d5").unwrap()));
+    // Leaving out the "x" of a capture is not accepted either
+    assert_eq!(position.parse_san("ed5"), Err(SanError::IllegalMove("ed5".to_string())));
+}
+
+#[test]
+fn ambiguous_moves() {
+    let knights = position("r3k3/8/8/8/8/8/8/1N2KN2 w - - 0 1");
+    let rooks = position("4k3/8/8/R7/8/8/8/R3K3 w - - 0 1");
+    let queens = position("8/k7/8/8/1Q5Q/8/8/K6Q w - - 0 1");
+
+    assert_eq!(knights.parse_san("Nd2"), Err(SanError::AmbiguousMove("Nd2".to_string())));
+    assert_eq!(rooks.parse_san("Ra3"), Err(SanError::AmbiguousMove("Ra3".to_string())));
+    assert_eq!(queens.parse_san("Qhe4"), Err(SanError::AmbiguousMove("Qhe4".to_string())));
+    assert_eq!(queens.parse_san("Q4e4"), Err(SanError::AmbiguousMove("Q4e4".to_string())));
+}
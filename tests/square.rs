@@ -0,0 +1,140 @@
+// Square conversions, checked for every square rather than a few samples.
+
+extern crate chess_engine;
+
+use chess_engine::square::Square;
+
+const FILES: &str = "abcdefgh";
+const TILE: f64 = 100.0;
+
+fn all_squares() -> Vec<Square> {
+    (0..64).map(|index| Square::from_index(index).unwrap()).collect()
+}
+
+#[test]
+fn index_round_trip() {
+    for index in 0..64 {
+        assert_eq!(Square::from_index(index).unwrap().index(), index);
+    }
+
+    assert_eq!(Square::from_index(64), None);
+    assert_eq!(Square::from_index(u32::MAX), None);
+}
+
+#[test]
+fn algebraic_names_for_every_square() {
+    for (file, file_letter) in FILES.chars().enumerate() {
+        for rank in 1..9 {
+            let name = format!("{}{}", file_letter, rank);
+            let square = Square::from_algebraic(&name).unwrap();
+
+            assert_eq!(square.to_algebraic(), name);
+            assert_eq!(square.to_string(), name);
+            assert_eq!(square.file(), file as u32);
+            assert_eq!(square.rank(), rank - 1);
+            assert_eq!(square.index(), (8 - rank) * 8 + file as u32);
+        }
+    }
+}
+
+#[test]
+fn corners_match_the_board_layout() {
+    assert_eq!(Square::from_algebraic("a8").unwrap().index(), 0);
+    assert_eq!(Square::from_algebraic("h8").unwrap().index(), 7);
+    assert_eq!(Square::from_algebraic("a1").unwrap().index(), 56);
+    assert_eq!(Square::from_algebraic("h1").unwrap().index(), 63);
+}
+
+#[test]
+fn names_are_unique() {
+    let mut names: Vec<String> = all_squares().iter().map(|square| square.to_algebraic()).collect();
+
+    names.sort();
+    names.dedup();
+
+    assert_eq!(names.len(), 64);
+    // The old tile lookup added file and rank, which made these two collide
+    assert_ne!(Square::from_algebraic("b1"), Square::from_algebraic("a2"));
+}
+
+#[test]
+fn malformed_names_are_rejected() {
+    for name in ["", "-", "a", "a0", "a9", "i1", "A1", "1a", "a10", "e4 ", " e4", "aa"].iter() {
+        assert_eq!(Square::from_algebraic(name), None, "{:?}", name);
+    }
+}
+
+#[test]
+fn file_rank_round_trip() {
+    for square in all_squares() {
+        assert_eq!(Square::from_file_rank(square.file(), square.rank()), Some(square));
+    }
+
+    assert_eq!(Square::from_file_rank(8, 0), None);
+    assert_eq!(Square::from_file_rank(0, 8), None);
+}
+
+#[test]
+fn offsets_stay_on_the_board() {
+    for square in all_squares() {
+        for file_offset in -8..9 {
+            for rank_offset in -8..9 {
+                let file = square.file() as i32 + file_offset;
+                let rank = square.rank() as i32 + rank_offset;
+                let on_board = (0..8).contains(&file) && (0..8).contains(&rank);
+
+                match square.offset(file_offset, rank_offset) {
+                    Some(target) => {
+                        assert!(on_board);
+                        assert_eq!(target.file() as i32, file);
+                        assert_eq!(target.rank() as i32, rank);
+                    }
+                    None => assert!(!on_board, "{} by ({}, {})", square, file_offset, rank_offset),
+                }
+            }
+        }
+    }
+}
+
+#[test]
+fn offset_directions() {
+    let e4 = Square::from_algebraic("e4").unwrap();
+
+    assert_eq!(e4.offset(0, 1).unwrap().to_algebraic(), "e5");
+    assert_eq!(e4.offset(1, 0).unwrap().to_algebraic(), "f4");
+    assert_eq!(e4.offset(-4, -3).unwrap().to_algebraic(), "a1");
+    assert_eq!(e4.offset(-5, 0), None);
+}
+
+#[test]
+fn pixels_round_trip_in_both_orientations() {
+    for &flipped in [false, true].iter() {
+        for square in all_squares() {
+            let (x, y) = square.to_pixel(TILE, flipped);
+
+            // Every corner of the tile and its centre map back to the square
+            for &(dx, dy) in [(0.0, 0.0), (99.9, 0.0), (0.0, 99.9), (99.9, 99.9), (50.0, 50.0)].iter() {
+                assert_eq!(Square::from_pixel(x + dx, y + dy, TILE, flipped), Some(square));
+            }
+        }
+    }
+}
+
+#[test]
+fn orientation_decides_the_top_left_square() {
+    assert_eq!(Square::from_pixel(0.0, 0.0, TILE, false).unwrap().to_algebraic(), "a8");
+    assert_eq!(Square::from_pixel(0.0, 0.0, TILE, true).unwrap().to_algebraic(), "h1");
+    assert_eq!(Square::from_pixel(0.0, 799.0, TILE, false).unwrap().to_algebraic(), "a1");
+    assert_eq!(Square::from_pixel(0.0, 799.0, TILE, true).unwrap().to_algebraic(), "h8");
+
+    // Each rank is on its own row of tiles, y = 0 and y = 100 differ
+    assert_ne!(Square::from_pixel(0.0, 0.0, TILE, false), Square::from_pixel(0.0, 100.0, TILE, false));
+}
+
+#[test]
+fn pixels_off_the_board() {
+    for &(x, y) in [(-1.0, 0.0), (0.0, -1.0), (800.0, 0.0), (0.0, 800.0), (850.0, 300.0)].iter() {
+        assert_eq!(Square::from_pixel(x, y, TILE, false), None);
+        assert_eq!(Square::from_pixel(x, y, TILE, true), None);
+    }
+}
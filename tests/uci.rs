@@ -0,0 +1,79 @@
+// UCI moves: writing them with to_uci, reading them back with parse_uci, and
+// the difference between a malformed string and a move that isn't legal.
+
+extern crate chess_engine;
+
+use chess_engine::fen::{Fen, START_POSITION};
+use chess_engine::position::Position;
+use chess_engine::uci::UciError;
+
+fn position(fen: &str) -> Position {
+    Fen { fen_string: fen.to_string() }.parse().unwrap()
+}
+
+#[test]
+fn every_legal_move_round_trips() {
+    for fen in [
+        START_POSITION,
+        "r3k2r/p1ppqpb1/bn2pnp1/3PN3/1p2P3/2N2Q1p/PPPBBPPP/R3K2R w KQkq - 0 1",
+        "r3k2r/Pppp1ppp/1b3nbN/nP6/BBP1P3/q4N2/Pp1P2PP/R2Q1RK1 w kq - 0 1",
+        "rnbq1k1r/pp1Pbppp/2p5/8/2B5/8/PPP1NnPP/RNBQK2R w KQ - 1 8",
+    ].iter() {
+        let position = position(fen);
+
+        for chess_move in position.get_legal_moves() {
+            assert_eq!(position.parse_uci(&chess_move.to_uci()), Ok(chess_move), "{} in {}", chess_move.to_uci(), fen);
+        }
+    }
+}
+
+#[test]
+fn promotions_write_their_piece() {
+    let position = position("3r2k1/4P3/8/8/8/8/8/4K3 w - - 0 1");
+
+    for uci in ["e7e8q", "e7e8r", "e7e8b", "e7e8n", "e7d8q", "e7d8n"].iter() {
+        assert_eq!(position.parse_uci(uci).unwrap().to_uci(), *uci);
+    }
+}
+
+#[test]
+fn castling_is_the_king_move() {
+    let position = position("r3k2r/8/8/8/8/8/8/R3K2R w KQkq - 0 1");
+
+    assert_eq!(position.parse_uci("e1g1").unwrap().to_uci(), "e1g1");
+    assert_eq!(position.parse_uci("e1c1").unwrap().to_uci(), "e1c1");
+}
+
+#[test]
+fn malformed_moves() {
+    let start = position(START_POSITION);
+    let promotion = position("3k4/4P3/8/8/8/8/8/4K3 w - - 0 1");
+
+    // Wrong length, squares off the board and characters that aren't ASCII
+    for uci in ["", "e2", "e2e", "e2e4qq", "e2e4 ", "i2e4", "e0e4", "e2e9", "é2e4"].iter() {
+        assert_eq!(start.parse_uci(uci), Err(UciError::InvalidSyntax(uci.to_string())), "{:?}", uci);
+    }
+
+    // A pawn can't promote to a king or stay a pawn, and the letter is lowercase
+    for uci in ["e7e8k", "e7e8p", "e7e8x", "e7e8Q"].iter() {
+        assert_eq!(promotion.parse_uci(uci), Err(UciError::InvalidSyntax(uci.to_string())), "{:?}", uci);
+    }
+}
+
+#[test]
+fn illegal_moves() {
+    let start = position(START_POSITION);
+
+    // Black's pawn while white is to move, an empty square, and moves the pieces can't make
+    for uci in ["e7e5", "e4e5", "e2e5", "g1g3", "e1g1", "e2e4q"].iter() {
+        assert_eq!(start.parse_uci(uci), Err(UciError::IllegalMove(uci.to_string())), "{:?}", uci);
+    }
+}
+
+#[test]
+fn promotion_needs_its_piece() {
+    let position = position("3k4/4P3/8/8/8/8/8/4K3 w - - 0 1");
+
+    assert_eq!(position.parse_uci("e7e8"), Err(UciError::IllegalMove("e7e8".to_string())));
+    assert!(position.parse_uci("e7e8q").is_ok());
+}